@@ -4,7 +4,7 @@
 CONFIG_PATH=/data/options.json
 
 if [ -f "$CONFIG_PATH" ]; then
-    for key in SURREAL_URL SHOPIFY_URL SHOPIFY_ACCESS_TOKEN ETSY_KEYSTRING ETSY_SECRET ETSY_SHOP_ID; do
+    for key in SURREAL_URL SHOPIFY_URL SHOPIFY_ACCESS_TOKEN ETSY_KEYSTRING ETSY_SECRET ETSY_SHOP_ID DISPLAY_TZ LOG_BUFFER_MAX AUTO_REFRESH_MINUTES URGENT_WITHIN_DAYS WARNING_WITHIN_DAYS BASE_CURRENCY; do
         val=$(bashio::jq "$CONFIG_PATH" ".$key // empty")
         if [ -n "$val" ]; then
             export "$key=$val"
@@ -1,42 +1,252 @@
 //! Server functions bridging client UI to server-side API/DB logic.
 //! These are callable from both web (WASM) and desktop clients.
 
+use chrono::{DateTime, Utc};
 use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
-use crate::model::{Order, PieceCostRow};
+use crate::model::{EtsyTokenStatus, Order, OrderSource, PieceCostRow, Settings, SourceToggles, UiPrefs};
 
 /// Result of fetching orders from all sources.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FetchOrdersResult {
     pub orders: Vec<Order>,
     pub errors: Vec<String>,
+    /// Some source failed because its credentials aren't set, so the UI can show setup help.
+    #[serde(default)]
+    pub not_configured: bool,
+}
+
+/// Structured counts for one refresh, for the UI and for monitoring the server endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FetchSummary {
+    pub total: usize,
+    /// Order count per source ("Shopify", "Etsy").
+    pub by_source: BTreeMap<String, usize>,
+    /// Order count per status label.
+    pub by_status: BTreeMap<String, usize>,
+    /// Due within the urgent threshold ([Settings::urgent_within_days]), overdue included.
+    /// Only orders the dashboard stats cover count ([Order::counts_in_stats]): empty,
+    /// cancelled, refunded, test and archived orders never count as urgent or overdue.
+    pub urgent: usize,
+    pub overdue: usize,
+    pub errors: Vec<String>,
+}
+
+#[cfg(feature = "server")]
+impl FetchSummary {
+    /// `tz` decides where "today" starts for the urgent/overdue counts; `archived_ids` are
+    /// left out of them.
+    pub fn from_result(
+        result: &FetchOrdersResult,
+        tz: chrono_tz::Tz,
+        thresholds: crate::model::DueThresholds,
+        archived_ids: &[String],
+    ) -> Self {
+        let mut summary = FetchSummary {
+            total: result.orders.len(),
+            errors: result.errors.clone(),
+            ..Default::default()
+        };
+        for order in &result.orders {
+            *summary.by_source.entry(order.source.display_name().to_string()).or_default() += 1;
+            *summary.by_status.entry(order.status.label().to_string()).or_default() += 1;
+            if !order.counts_in_stats(archived_ids) {
+                continue;
+            }
+            if order.is_urgent(tz, thresholds) {
+                summary.urgent += 1;
+            }
+            if order.is_overdue(tz) {
+                summary.overdue += 1;
+            }
+        }
+        summary
+    }
+}
+
+/// [FetchOrdersResult] plus its [FetchSummary].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchOrdersWithSummary {
+    pub result: FetchOrdersResult,
+    pub summary: FetchSummary,
+}
+
+/// What `fetch_all_orders` should fetch. `Default` matches the dashboard's standard view.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FetchOptions {
+    /// How far back to look for orders, by creation date.
+    pub lookback_days: i64,
+    /// `None` keeps each source's native default (Shopify: any fulfillment status,
+    /// Etsy: unshipped receipts only). `Some(_)` applies the same rule to every source.
+    pub include_shipped: Option<bool>,
+    /// Sources to query. Sources disabled in [SourceToggles] are skipped regardless.
+    pub sources: Vec<OrderSource>,
+    /// Drop duplicate orders (same source and id) from the combined result.
+    pub dedup: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            lookback_days: 60,
+            include_shipped: None,
+            sources: vec![OrderSource::Shopify, OrderSource::Etsy],
+            dedup: false,
+        }
+    }
 }
 
 /// Fetch orders from Shopify and Etsy. Errors from individual sources are
-/// collected in `errors` so partial results are still returned.
+/// collected in `errors` so partial results are still returned. Sources
+/// disabled in [SourceToggles] are skipped without an error. Sources are
+/// fetched concurrently, at most [MAX_CONCURRENT_FETCHES] at a time.
 #[server]
-pub async fn fetch_all_orders() -> Result<FetchOrdersResult, ServerFnError> {
+pub async fn fetch_all_orders(options: FetchOptions) -> Result<FetchOrdersResult, ServerFnError> {
+    let started = std::time::Instant::now();
+    let toggles = crate::config::load_source_toggles();
+    let lookback_days = options.lookback_days.max(1);
+    let limit = std::sync::Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_FETCHES));
+    let mut fetches = tokio::task::JoinSet::new();
+
+    if !options.sources.contains(&OrderSource::Shopify) {
+        // Not requested by the caller.
+    } else if toggles.fetch_shopify {
+        let include_shipped = options.include_shipped.unwrap_or(true);
+        spawn_source_fetch(
+            &mut fetches,
+            &limit,
+            OrderSource::Shopify,
+            crate::shopify::fetch_shopify_orders(lookback_days, include_shipped),
+        );
+    } else {
+        crate::log::app_log("INFO", "Shopify: disabled in settings, skipping");
+    }
+
+    if !options.sources.contains(&OrderSource::Etsy) {
+        // Not requested by the caller.
+    } else if toggles.fetch_etsy {
+        let include_shipped = options.include_shipped.unwrap_or(false);
+        spawn_source_fetch(
+            &mut fetches,
+            &limit,
+            OrderSource::Etsy,
+            crate::etsy::fetch_etsy_orders(lookback_days, include_shipped),
+        );
+    } else {
+        crate::log::app_log("INFO", "Etsy: disabled in settings, skipping");
+    }
+
     let mut all_orders = Vec::new();
     let mut errors = Vec::new();
-
-    match crate::shopify::fetch_shopify_orders().await {
-        Ok(shopify_orders) => all_orders.extend(shopify_orders),
-        Err(e) => errors.push(format!("Shopify: {}", e)),
+    let mut not_configured = false;
+    while let Some(joined) = fetches.join_next().await {
+        match joined {
+            Ok((_, Ok(orders))) => all_orders.extend(orders),
+            Ok((source, Err(e))) => {
+                not_configured |= matches!(e, crate::http_util::FetchError::NotConfigured(_));
+                errors.push(format!("{}: {}", source.display_name(), fetch_error_message(&source, &e)))
+            }
+            Err(e) => errors.push(format!("Fetch task failed: {}", e)),
+        }
     }
+    // Completion order is nondeterministic; keep error reporting stable.
+    errors.sort();
 
-    match crate::etsy::fetch_etsy_orders().await {
-        Ok(etsy_orders) => all_orders.extend(etsy_orders),
-        Err(e) => errors.push(format!("Etsy: {}", e)),
+    if options.dedup {
+        let mut seen = std::collections::HashSet::new();
+        all_orders.retain(|o| seen.insert((o.source.clone(), o.id.clone())));
     }
 
+    apply_due_date_overrides(&mut all_orders).await;
+
     all_orders.sort_by(|a, b| a.due_date.cmp(&b.due_date));
+    crate::log::app_log(
+        "INFO",
+        format!(
+            "Fetched {} orders ({} errors) in {} ms",
+            all_orders.len(),
+            errors.len(),
+            started.elapsed().as_millis()
+        ),
+    );
     Ok(FetchOrdersResult {
         orders: all_orders,
         errors,
+        not_configured,
     })
 }
 
+/// Same as [fetch_all_orders], with a [FetchSummary] computed from the result.
+#[server]
+pub async fn fetch_orders_with_summary(options: FetchOptions) -> Result<FetchOrdersWithSummary, ServerFnError> {
+    let result = fetch_all_orders(options).await?;
+    let thresholds = crate::config::load_settings().due_thresholds();
+    let archived = match crate::db::ensure_db_init().await {
+        Ok(()) => crate::db::load_archived_order_ids().await.unwrap_or_default(),
+        Err(e) => {
+            crate::log::app_log("ERROR", format!("Fetch summary: archived orders not loaded ({})", e));
+            Vec::new()
+        }
+    };
+    let summary = FetchSummary::from_result(&result, crate::config::display_tz(), thresholds, &archived);
+    Ok(FetchOrdersWithSummary { result, summary })
+}
+
+/// Upper bound on source/shop fetches running at once.
+#[cfg(feature = "server")]
+const MAX_CONCURRENT_FETCHES: usize = 4;
+
+#[cfg(feature = "server")]
+type SourceFetchResult = (OrderSource, Result<Vec<Order>, crate::http_util::FetchError>);
+
+/// A source's fetch error as the UI shows it, in each source's own wording.
+#[cfg(feature = "server")]
+fn fetch_error_message(source: &OrderSource, e: &crate::http_util::FetchError) -> String {
+    match source {
+        OrderSource::Shopify => crate::shopify::fetch_error_message(e),
+        OrderSource::Etsy => crate::etsy::fetch_error_message(e),
+    }
+}
+
+/// Run one source fetch on the join set once a concurrency permit is available.
+#[cfg(feature = "server")]
+fn spawn_source_fetch<F>(
+    fetches: &mut tokio::task::JoinSet<SourceFetchResult>,
+    limit: &std::sync::Arc<tokio::sync::Semaphore>,
+    source: OrderSource,
+    fetch: F,
+) where
+    F: std::future::Future<Output = Result<Vec<Order>, crate::http_util::FetchError>> + Send + 'static,
+{
+    let limit = limit.clone();
+    fetches.spawn(async move {
+        let _permit = limit.acquire_owned().await;
+        (source, fetch.await)
+    });
+}
+
+/// Replace computed due dates with saved manual overrides. A DB failure only logs:
+/// orders still load with their computed dates.
+#[cfg(feature = "server")]
+async fn apply_due_date_overrides(orders: &mut [Order]) {
+    let overrides = match crate::db::ensure_db_init().await {
+        Ok(()) => crate::db::load_due_date_overrides().await,
+        Err(e) => Err(e),
+    };
+    match overrides {
+        Ok(overrides) => {
+            for order in orders.iter_mut() {
+                if let Some(due) = overrides.get(&order.id) {
+                    order.apply_due_date_override(*due);
+                }
+            }
+        }
+        Err(e) => crate::log::app_log("INFO", format!("Due date overrides not applied: {}", e)),
+    }
+}
+
 /// Load piece costs from SurrealDB (initialises the DB connection on first call).
 #[server]
 pub async fn fetch_piece_costs() -> Result<Vec<PieceCostRow>, ServerFnError> {
@@ -48,9 +258,181 @@ pub async fn fetch_piece_costs() -> Result<Vec<PieceCostRow>, ServerFnError> {
         .map_err(|e| ServerFnError::new(e))
 }
 
+/// Printable work order of unshipped items due within `due_within_days` (0 = due today
+/// and overdue), grouped by metal. Fetches orders fresh; archived orders are left out.
+#[server]
+pub async fn daily_queue_html(due_within_days: i64) -> Result<String, ServerFnError> {
+    let result = fetch_all_orders(FetchOptions::default()).await?;
+    for e in &result.errors {
+        crate::log::app_log("ERROR", format!("Daily queue: {}", e));
+    }
+    let (piece_costs, archived) = match crate::db::ensure_db_init().await {
+        Ok(()) => (
+            crate::db::load_piece_costs().await.unwrap_or_default(),
+            crate::db::load_archived_order_ids().await.unwrap_or_default(),
+        ),
+        Err(e) => {
+            crate::log::app_log("ERROR", format!("Daily queue: no catalog totals ({})", e));
+            (Vec::new(), Vec::new())
+        }
+    };
+    let orders: Vec<Order> = result
+        .orders
+        .into_iter()
+        .filter(|o| !o.is_test && !archived.contains(&o.id))
+        .collect();
+    let settings = crate::config::load_settings();
+    Ok(crate::daily_queue::render(
+        &orders,
+        &piece_costs,
+        crate::config::display_tz(),
+        due_within_days,
+        settings.cost_currency(),
+    ))
+}
+
+/// Ids of orders the user has archived (hidden from the active views).
+#[server]
+pub async fn fetch_archived_order_ids() -> Result<Vec<String>, ServerFnError> {
+    crate::db::ensure_db_init()
+        .await
+        .map_err(|e| ServerFnError::new(e))?;
+    crate::db::load_archived_order_ids()
+        .await
+        .map_err(|e| ServerFnError::new(e))
+}
+
+/// Archive an order by `Order.id`; survives refetches.
+#[server]
+pub async fn archive_order(order_id: String) -> Result<(), ServerFnError> {
+    crate::db::ensure_db_init()
+        .await
+        .map_err(|e| ServerFnError::new(e))?;
+    crate::db::archive_order(order_id)
+        .await
+        .map_err(|e| ServerFnError::new(e))
+}
+
+/// Restore an archived order to the active views.
+#[server]
+pub async fn unarchive_order(order_id: String) -> Result<(), ServerFnError> {
+    crate::db::ensure_db_init()
+        .await
+        .map_err(|e| ServerFnError::new(e))?;
+    crate::db::unarchive_order(order_id)
+        .await
+        .map_err(|e| ServerFnError::new(e))
+}
+
+/// All per-order notes as `order_id -> note`.
+#[server]
+pub async fn load_order_notes() -> Result<HashMap<String, String>, ServerFnError> {
+    crate::db::ensure_db_init()
+        .await
+        .map_err(|e| ServerFnError::new(e))?;
+    crate::db::load_order_notes()
+        .await
+        .map_err(|e| ServerFnError::new(e))
+}
+
+/// Save (or clear, when empty) the note for an order.
+#[server]
+pub async fn save_order_note(order_id: String, note: String) -> Result<(), ServerFnError> {
+    crate::db::ensure_db_init()
+        .await
+        .map_err(|e| ServerFnError::new(e))?;
+    crate::db::save_order_note(order_id, note)
+        .await
+        .map_err(|e| ServerFnError::new(e))
+}
+
+/// Manually set an order's due date; applied over the computed date on every fetch.
+#[server]
+pub async fn set_due_date_override(order_id: String, due: DateTime<Utc>) -> Result<(), ServerFnError> {
+    crate::db::ensure_db_init()
+        .await
+        .map_err(|e| ServerFnError::new(e))?;
+    crate::db::set_due_date_override(order_id, due)
+        .await
+        .map_err(|e| ServerFnError::new(e))
+}
+
+/// Remove a manual due date so the computed one applies again.
+#[server]
+pub async fn clear_due_date_override(order_id: String) -> Result<(), ServerFnError> {
+    crate::db::ensure_db_init()
+        .await
+        .map_err(|e| ServerFnError::new(e))?;
+    crate::db::clear_due_date_override(order_id)
+        .await
+        .map_err(|e| ServerFnError::new(e))
+}
+
+/// Mark a Shopify order fulfilled in Shopify. `location` limits it to the items assigned
+/// to that location (for orders split across locations); `None` fulfills everything open.
+/// Returns how many fulfillment orders are still open, so 0 means the order is done.
+#[server]
+pub async fn mark_shopify_fulfilled(order_id: String, location: Option<String>) -> Result<usize, ServerFnError> {
+    crate::shopify::mark_shopify_fulfilled(&order_id, location.as_deref())
+        .await
+        .map_err(|e| ServerFnError::new(e.message_with_body()))
+}
+
 /// Save an Etsy OAuth refresh token (persisted to disk on the server).
 #[server]
 pub async fn save_etsy_token(token: String) -> Result<(), ServerFnError> {
     crate::etsy::save_etsy_refresh_token(token)
         .map_err(|e| ServerFnError::new(e))
 }
+
+/// Whether the saved Etsy token is healthy, close to running out, or needs replacing.
+#[server]
+pub async fn etsy_token_status() -> Result<EtsyTokenStatus, ServerFnError> {
+    Ok(crate::etsy::etsy_token_status())
+}
+
+/// Load all persisted settings (env defaults for anything never saved).
+#[server]
+pub async fn load_settings() -> Result<Settings, ServerFnError> {
+    Ok(crate::config::load_settings())
+}
+
+/// Persist all settings at once.
+#[server]
+pub async fn save_settings(settings: Settings) -> Result<(), ServerFnError> {
+    crate::config::save_settings(&settings)
+        .map_err(|e| ServerFnError::new(e))
+}
+
+/// Set the auto-refresh interval (0 = off), leaving the other settings as stored.
+#[server]
+pub async fn save_auto_refresh_minutes(minutes: u32) -> Result<(), ServerFnError> {
+    crate::config::save_auto_refresh_minutes(minutes)
+        .map_err(|e| ServerFnError::new(e))
+}
+
+/// Load persisted view preferences (filter + sort).
+#[server]
+pub async fn load_ui_prefs() -> Result<UiPrefs, ServerFnError> {
+    Ok(crate::config::load_ui_prefs())
+}
+
+/// Persist view preferences so they are restored on the next load.
+#[server]
+pub async fn save_ui_prefs(prefs: UiPrefs) -> Result<(), ServerFnError> {
+    crate::config::save_ui_prefs(&prefs)
+        .map_err(|e| ServerFnError::new(e))
+}
+
+/// Load which order sources are enabled.
+#[server]
+pub async fn load_source_toggles() -> Result<SourceToggles, ServerFnError> {
+    Ok(crate::config::load_source_toggles())
+}
+
+/// Enable/disable order sources; takes effect on the next fetch.
+#[server]
+pub async fn save_source_toggles(toggles: SourceToggles) -> Result<(), ServerFnError> {
+    crate::config::save_source_toggles(&toggles)
+        .map_err(|e| ServerFnError::new(e))
+}
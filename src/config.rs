@@ -0,0 +1,142 @@
+//! On-disk JSON config files (server-only): Etsy OAuth state and [Settings] (UI preferences,
+//! source toggles, auto-refresh, due thresholds, base currency).
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono_tz::Tz;
+
+use crate::model::{parse_display_tz, DueThresholds, Settings, SourceToggles, UiPrefs, COST_CURRENCY};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Held across each read-modify-write of [SETTINGS_FILE], so saves of different sections
+/// (UI prefs, source toggles, auto-refresh) can't overwrite each other's fields.
+static SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Resolve the path for a config file: HA add-on `/data/` when present, else the system config dir.
+pub fn config_file_path(file_name: &str) -> Option<PathBuf> {
+    // HA add-on: persistent storage at /data/
+    let ha_dir = PathBuf::from("/data");
+    if ha_dir.exists() {
+        return Some(ha_dir.join(file_name));
+    }
+    // Desktop / local dev: system config directory
+    directories::ProjectDirs::from("com", "KingsOfAlchemy", "OrderTracker")
+        .map(|d| d.config_dir().join(file_name))
+}
+
+/// Write `data` to a config file, creating the parent directory if needed.
+pub fn write_config_file(file_name: &str, data: &str) -> Result<(), String> {
+    let path = config_file_path(file_name).ok_or_else(|| "No config dir".to_string())?;
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    std::fs::write(&path, data).map_err(|e| e.to_string())
+}
+
+/// Read a config file; `None` when missing or unreadable.
+pub fn read_config_file(file_name: &str) -> Option<String> {
+    let path = config_file_path(file_name)?;
+    std::fs::read_to_string(&path).ok()
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(file_name: &str) -> Option<T> {
+    read_config_file(file_name).and_then(|data| serde_json::from_str(&data).ok())
+}
+
+/// Settings as stored, without env fallbacks.
+fn read_settings() -> Settings {
+    read_json(SETTINGS_FILE).unwrap_or_default()
+}
+
+/// An env var parsed as `T`; `None` when unset or malformed.
+fn env_parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name).ok().and_then(|v| v.trim().parse().ok())
+}
+
+/// Settings for the UI, with anything never set filled from the env vars named on
+/// [Settings] or the built-in defaults. These env-derived values are only for display;
+/// saves leave unset fields unset, so later env changes still apply.
+pub fn load_settings() -> Settings {
+    let mut settings = read_settings();
+    let defaults = DueThresholds::default();
+    if settings.ui.display_tz.is_none() {
+        settings.ui.display_tz = Some(display_tz().name().to_string());
+    }
+    settings.auto_refresh_minutes = settings
+        .auto_refresh_minutes
+        .or_else(|| env_parse("AUTO_REFRESH_MINUTES"))
+        .or(Some(0));
+    settings.urgent_within_days = settings
+        .urgent_within_days
+        .or_else(|| env_parse("URGENT_WITHIN_DAYS"))
+        .or(Some(defaults.urgent_within_days));
+    settings.warning_within_days = settings
+        .warning_within_days
+        .or_else(|| env_parse("WARNING_WITHIN_DAYS"))
+        .or(Some(defaults.warning_within_days));
+    settings.base_currency = settings
+        .base_currency
+        .or_else(|| env_parse::<String>("BASE_CURRENCY").filter(|c| !c.is_empty()))
+        .map(|c| c.to_uppercase())
+        .or_else(|| Some(COST_CURRENCY.to_string()));
+    settings
+}
+
+/// Apply `change` to the stored settings and write them back, under [SETTINGS_LOCK].
+/// Rejects an unknown timezone rather than saving it.
+fn update_settings(change: impl FnOnce(&mut Settings)) -> Result<(), String> {
+    let _guard = SETTINGS_LOCK.lock().map_err(|_| "Settings lock poisoned".to_string())?;
+    let mut settings = read_settings();
+    change(&mut settings);
+    if let Some(tz) = &settings.ui.display_tz {
+        parse_display_tz(tz)?;
+    }
+    let data = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    write_config_file(SETTINGS_FILE, &data)
+}
+
+/// Replace all settings at once.
+pub fn save_settings(settings: &Settings) -> Result<(), String> {
+    update_settings(|stored| *stored = settings.clone())
+}
+
+/// Load saved UI preferences (defaults when nothing has been saved yet).
+pub fn load_ui_prefs() -> UiPrefs {
+    load_settings().ui
+}
+
+/// Save UI preferences; a `display_tz` of `None` keeps the stored zone.
+pub fn save_ui_prefs(prefs: &UiPrefs) -> Result<(), String> {
+    update_settings(|settings| {
+        let display_tz = prefs.display_tz.clone().or_else(|| settings.ui.display_tz.take());
+        settings.ui = UiPrefs {
+            display_tz,
+            ..prefs.clone()
+        };
+    })
+}
+
+pub fn save_auto_refresh_minutes(minutes: u32) -> Result<(), String> {
+    update_settings(|settings| settings.auto_refresh_minutes = Some(minutes))
+}
+
+/// Zone for server-side day counts: the saved preference, else `DISPLAY_TZ`, else UTC.
+pub fn display_tz() -> Tz {
+    read_settings()
+        .ui
+        .display_tz
+        .or_else(|| std::env::var("DISPLAY_TZ").ok())
+        .and_then(|name| parse_display_tz(&name).ok())
+        .unwrap_or(Tz::UTC)
+}
+
+/// Load which sources to fetch (both enabled when nothing has been saved yet).
+pub fn load_source_toggles() -> SourceToggles {
+    read_settings().sources
+}
+
+pub fn save_source_toggles(toggles: &SourceToggles) -> Result<(), String> {
+    update_settings(|settings| settings.sources = toggles.clone())
+}
@@ -0,0 +1,148 @@
+//! Printable shop-floor work order (server-only): items due soon, grouped by metal.
+
+use chrono_tz::Tz;
+
+use crate::model::{format_money, lookup_piece_cost, MetalType, Order, OrderItem, PieceCostRow};
+
+/// One line of the work order.
+struct QueueLine<'a> {
+    order: &'a Order,
+    item: &'a OrderItem,
+    days_left: i64,
+}
+
+/// Catalog totals for one metal group.
+#[derive(Default)]
+struct MetalTotals {
+    pieces: u32,
+    weight_g: f64,
+    cost_usd: f64,
+    unmatched: usize,
+}
+
+/// Unshipped items of live orders due within `due_within_days` (0 = today and overdue),
+/// grouped by metal and sorted by due date, as a standalone print-ready HTML page. Catalog
+/// cost totals are shown in `cost_currency`.
+pub fn render(
+    orders: &[Order],
+    piece_costs: &[PieceCostRow],
+    tz: Tz,
+    due_within_days: i64,
+    cost_currency: &str,
+) -> String {
+    let mut lines: Vec<QueueLine> = orders
+        .iter()
+        .filter(|o| !o.is_inactive())
+        .filter_map(|o| {
+            let days_left = o.days_until_due(tz);
+            (days_left <= due_within_days).then_some((o, days_left))
+        })
+        .flat_map(|(order, days_left)| {
+            order
+                .items
+                .iter()
+                .filter(move |item| !order.item_shipped(item))
+                .map(move |item| QueueLine { order, item, days_left })
+        })
+        .collect();
+    lines.sort_by(|a, b| a.order.due_date.cmp(&b.order.due_date));
+
+    // Metal groups in name order, with unidentified pieces last.
+    let mut metals: Vec<String> = lines.iter().map(|l| l.item.metal_label()).collect();
+    metals.sort();
+    metals.dedup();
+    let unknown = MetalType::Unknown.display_name();
+    metals.sort_by_key(|m| m.as_str() == unknown);
+
+    let now = chrono::Utc::now().with_timezone(&tz);
+    let scope = if due_within_days <= 0 {
+        "due today and overdue".to_string()
+    } else {
+        format!("due within {} days", due_within_days)
+    };
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Today's queue</title><style>");
+    html.push_str(QUEUE_CSS);
+    html.push_str("</style></head><body>");
+    html.push_str(&format!(
+        "<h1>Work queue &ndash; {}</h1><p class=\"meta\">{} pieces {} &middot; printed {}</p>",
+        now.format("%a %b %d, %Y"),
+        lines.iter().map(|l| l.item.quantity).sum::<u32>(),
+        scope,
+        now.format("%H:%M %Z")
+    ));
+    if lines.is_empty() {
+        html.push_str("<p>Nothing due.</p>");
+    }
+
+    for metal in &metals {
+        let group: Vec<&QueueLine> = lines.iter().filter(|l| &l.item.metal_label() == metal).collect();
+        let mut totals = MetalTotals::default();
+        html.push_str(&format!("<h2>{}</h2><table><thead><tr>", escape_html(metal)));
+        html.push_str("<th>Qty</th><th>Item</th><th>Size</th><th>Order</th><th>Customer</th><th>Due</th><th>Done</th>");
+        html.push_str("</tr></thead><tbody>");
+        for line in group {
+            let item = line.item;
+            totals.pieces += item.quantity;
+            match lookup_piece_cost(item, piece_costs) {
+                Some(cw) => {
+                    totals.weight_g += cw.weight_g * item.quantity as f64;
+                    totals.cost_usd += cw.cost_usd * item.quantity as f64;
+                }
+                None => totals.unmatched += 1,
+            }
+            let name = match &item.variant_info {
+                Some(variant) => format!("{}<div class=\"variant\">{}</div>", escape_html(&item.name), escape_html(variant)),
+                None => escape_html(&item.name),
+            };
+            let due = match line.days_left {
+                d if d < 0 => format!("<strong>{} overdue</strong>", -d),
+                0 => "<strong>today</strong>".to_string(),
+                d => format!("{} days", d),
+            };
+            html.push_str(&format!(
+                "<tr><td class=\"qty\">{}</td><td>{}</td><td class=\"size\">{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"check\">&#9744;</td></tr>",
+                item.quantity,
+                name,
+                escape_html(item.ring_size.as_deref().unwrap_or("")),
+                escape_html(&line.order.order_number),
+                escape_html(&line.order.customer_name),
+                due
+            ));
+        }
+        let unmatched = if totals.unmatched > 0 {
+            format!(" ({} not in catalog)", totals.unmatched)
+        } else {
+            String::new()
+        };
+        html.push_str(&format!(
+            "</tbody></table><p class=\"totals\">{} pieces &middot; {:.1} g &middot; {}{}</p>",
+            totals.pieces,
+            totals.weight_g,
+            format_money(totals.cost_usd, cost_currency),
+            unmatched
+        ));
+    }
+    html.push_str("</body></html>");
+    html
+}
+
+const QUEUE_CSS: &str = "\
+body { font-family: sans-serif; font-size: 11pt; margin: 1.5cm; color: #000; }\
+h1 { font-size: 16pt; margin: 0; }\
+h2 { font-size: 13pt; margin: 1.2em 0 0.3em; border-bottom: 2px solid #000; }\
+.meta { color: #444; margin: 0.2em 0 1em; }\
+table { width: 100%; border-collapse: collapse; }\
+th, td { text-align: left; padding: 3px 6px; border-bottom: 1px solid #ccc; vertical-align: top; }\
+.qty, .size { font-weight: bold; white-space: nowrap; }\
+.variant { font-size: 9pt; color: #444; }\
+.check { font-size: 14pt; text-align: center; }\
+.totals { text-align: right; font-weight: bold; margin: 0.3em 0; }\
+@media print { body { margin: 0; } h2 { break-after: avoid; } tr { break-inside: avoid; } }";
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,6 +1,8 @@
 //! SurrealDB connection singleton (server-only).
 //! Set SURREAL_URL in env (e.g. ws://127.0.0.1:8000) and call ensure_db_init() before querying.
 
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::sync::LazyLock;
 use surrealdb::engine::remote::ws::{Client, Ws, Wss};
 use surrealdb::Surreal;
@@ -11,34 +13,79 @@ const DB_NAME: &str = "jewelry_calculator";
 /// Singleton DB; connect with ensure_db_init() at startup when SURREAL_URL is set.
 pub static DB: LazyLock<Surreal<Client>> = LazyLock::new(Surreal::init);
 
-static DB_INIT: tokio::sync::OnceCell<()> = tokio::sync::OnceCell::const_new();
+/// Two-step, once-only init: connect, then select the namespace. Kept apart from [DB] so
+/// the once/retry behavior doesn't depend on a live server.
+struct InitOnce {
+    /// Set once the full init (connect + namespace selection) has succeeded.
+    done: tokio::sync::OnceCell<()>,
+    /// Set once the connect has succeeded. Kept apart from `done` because the client
+    /// can't be connected twice: a retry after a failed `use_ns` must skip the connect.
+    connected: tokio::sync::OnceCell<()>,
+}
+
+impl InitOnce {
+    const fn new() -> Self {
+        Self {
+            done: tokio::sync::OnceCell::const_new(),
+            connected: tokio::sync::OnceCell::const_new(),
+        }
+    }
+
+    /// Callers arriving while init is in flight wait for that attempt instead of starting
+    /// their own, later calls are no-ops, and a failed attempt leaves `done` empty so the
+    /// next call retries.
+    async fn run<C, S>(
+        &self,
+        connect: impl FnOnce() -> C,
+        select: impl FnOnce() -> S,
+    ) -> Result<(), String>
+    where
+        C: std::future::Future<Output = Result<(), String>>,
+        S: std::future::Future<Output = Result<(), String>>,
+    {
+        self.done
+            .get_or_try_init(|| async move {
+                self.connected.get_or_try_init(connect).await?;
+                select().await
+            })
+            .await
+            .map(|_| ())
+    }
+}
 
-/// Connect the singleton DB exactly once. Safe to call repeatedly (subsequent calls are no-ops).
+static DB_INIT: InitOnce = InitOnce::new();
+
+/// Connect the singleton DB exactly once. Safe to call repeatedly and concurrently:
+/// callers arriving while init is in flight wait for that attempt instead of starting
+/// their own, later calls are no-ops, and a failed attempt leaves the cell empty so the
+/// next call retries (e.g. once SurrealDB is up) rather than failing forever.
 pub async fn ensure_db_init() -> Result<(), String> {
     DB_INIT
-        .get_or_try_init(|| async {
-            let url = std::env::var("SURREAL_URL")
-                .map_err(|_| "SURREAL_URL not set".to_string())?;
-            let url = url.trim().to_string();
-            if url.is_empty() {
-                return Err("SURREAL_URL is empty".to_string());
-            }
-            let connect_result = if url.starts_with("wss") {
-                DB.connect::<Wss>(&url).await
-            } else {
-                DB.connect::<Ws>(&url).await
-            };
-            match &connect_result {
-                Ok(_) => eprintln!("Connected to SurrealDB at {}", url),
-                Err(e) => eprintln!("Failed connecting to {}: {:?}", url, e),
-            }
-            connect_result.map_err(|e| e.to_string())?;
+        .run(connect_db, || async {
             DB.use_ns(NS).use_db(DB_NAME).await.map_err(|e| e.to_string())?;
             eprintln!("Using NS: {}, DB: {}", NS, DB_NAME);
             Ok(())
         })
         .await
-        .map(|_| ())
+}
+
+async fn connect_db() -> Result<(), String> {
+    let url = std::env::var("SURREAL_URL")
+        .map_err(|_| "SURREAL_URL not set".to_string())?;
+    let url = url.trim().to_string();
+    if url.is_empty() {
+        return Err("SURREAL_URL is empty".to_string());
+    }
+    let connect_result = if url.starts_with("wss") {
+        DB.connect::<Wss>(&url).await
+    } else {
+        DB.connect::<Ws>(&url).await
+    };
+    match &connect_result {
+        Ok(_) => eprintln!("Connected to SurrealDB at {}", url),
+        Err(e) => eprintln!("Failed connecting to {}: {:?}", url, e),
+    }
+    connect_result.map_err(|e| e.to_string())
 }
 
 /// Load all piece_costs from the database (call after ensure_db_init()).
@@ -49,3 +96,215 @@ pub async fn load_piece_costs() -> Result<Vec<crate::model::PieceCostRow>, Strin
         .map_err(|e| e.to_string())?;
     Ok(rows)
 }
+
+// ---------------------------------------------------------------------------
+// Archived orders (manually hidden from the active views, keyed by Order.id)
+// ---------------------------------------------------------------------------
+
+const ARCHIVED_ORDERS: &str = "archived_orders";
+
+#[derive(Debug, Clone, surrealdb_types::SurrealValue)]
+struct ArchivedOrderRow {
+    order_id: String,
+    archived_at: String,
+}
+
+/// Ids of all archived orders.
+pub async fn load_archived_order_ids() -> Result<Vec<String>, String> {
+    let rows: Vec<ArchivedOrderRow> = DB
+        .select(ARCHIVED_ORDERS)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows.into_iter().map(|r| r.order_id).collect())
+}
+
+/// Mark an order archived. Idempotent.
+pub async fn archive_order(order_id: String) -> Result<(), String> {
+    let row = ArchivedOrderRow {
+        order_id: order_id.clone(),
+        archived_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let _: Option<ArchivedOrderRow> = DB
+        .upsert((ARCHIVED_ORDERS, order_id))
+        .content(row)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Remove an order from the archive. No-op if it wasn't archived.
+pub async fn unarchive_order(order_id: String) -> Result<(), String> {
+    let _: Option<ArchivedOrderRow> = DB
+        .delete((ARCHIVED_ORDERS, order_id))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Per-order notes (free text, keyed by Order.id)
+// ---------------------------------------------------------------------------
+
+const ORDER_NOTES: &str = "order_notes";
+
+#[derive(Debug, Clone, surrealdb_types::SurrealValue)]
+struct OrderNoteRow {
+    order_id: String,
+    note: String,
+    updated_at: String,
+}
+
+/// All saved notes as `order_id -> note`.
+pub async fn load_order_notes() -> Result<HashMap<String, String>, String> {
+    let rows: Vec<OrderNoteRow> = DB
+        .select(ORDER_NOTES)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows.into_iter().map(|r| (r.order_id, r.note)).collect())
+}
+
+/// Save a note for an order; an empty (whitespace-only) note deletes it.
+pub async fn save_order_note(order_id: String, note: String) -> Result<(), String> {
+    let note = note.trim().to_string();
+    if note.is_empty() {
+        let _: Option<OrderNoteRow> = DB
+            .delete((ORDER_NOTES, order_id))
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    let row = OrderNoteRow {
+        order_id: order_id.clone(),
+        note,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let _: Option<OrderNoteRow> = DB
+        .upsert((ORDER_NOTES, order_id))
+        .content(row)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Due date overrides (manual reschedules that survive refetches)
+// ---------------------------------------------------------------------------
+
+const DUE_DATE_OVERRIDES: &str = "due_date_overrides";
+
+#[derive(Debug, Clone, surrealdb_types::SurrealValue)]
+struct DueDateOverrideRow {
+    order_id: String,
+    /// RFC 3339 timestamp.
+    due: String,
+}
+
+/// All overrides as `order_id -> due date`. Rows with an unparseable date are skipped.
+pub async fn load_due_date_overrides() -> Result<HashMap<String, DateTime<Utc>>, String> {
+    let rows: Vec<DueDateOverrideRow> = DB
+        .select(DUE_DATE_OVERRIDES)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|r| {
+            let due = DateTime::parse_from_rfc3339(&r.due).ok()?.with_timezone(&Utc);
+            Some((r.order_id, due))
+        })
+        .collect())
+}
+
+pub async fn set_due_date_override(order_id: String, due: DateTime<Utc>) -> Result<(), String> {
+    let row = DueDateOverrideRow {
+        order_id: order_id.clone(),
+        due: due.to_rfc3339(),
+    };
+    let _: Option<DueDateOverrideRow> = DB
+        .upsert((DUE_DATE_OVERRIDES, order_id))
+        .content(row)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub async fn clear_due_date_override(order_id: String) -> Result<(), String> {
+    let _: Option<DueDateOverrideRow> = DB
+        .delete((DUE_DATE_OVERRIDES, order_id))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use surrealdb::engine::local::{Db, Mem};
+
+    #[tokio::test]
+    async fn concurrent_first_callers_init_once() {
+        static INIT: InitOnce = InitOnce::new();
+        static CONNECTS: AtomicUsize = AtomicUsize::new(0);
+        static SELECTS: AtomicUsize = AtomicUsize::new(0);
+        let db: Surreal<Db> = Surreal::init();
+
+        let callers: Vec<_> = (0..16)
+            .map(|_| {
+                let (conn, sel) = (db.clone(), db.clone());
+                tokio::spawn(async move {
+                    INIT.run(
+                        || async move {
+                            CONNECTS.fetch_add(1, Ordering::SeqCst);
+                            // Keep the first attempt in flight while the others arrive.
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            conn.connect::<Mem>(()).await.map_err(|e| e.to_string())
+                        },
+                        || async move {
+                            SELECTS.fetch_add(1, Ordering::SeqCst);
+                            sel.use_ns(NS).use_db(DB_NAME).await.map_err(|e| e.to_string())
+                        },
+                    )
+                    .await
+                })
+            })
+            .collect();
+        for caller in callers {
+            assert_eq!(caller.await.unwrap(), Ok(()));
+        }
+
+        assert_eq!(CONNECTS.load(Ordering::SeqCst), 1);
+        assert_eq!(SELECTS.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_init_is_retried() {
+        let init = InitOnce::new();
+        let db: &Surreal<Db> = &Surreal::init();
+        let connects = &AtomicUsize::new(0);
+        let connect = move || async move {
+            connects.fetch_add(1, Ordering::SeqCst);
+            db.connect::<Mem>(()).await.map_err(|e| e.to_string())
+        };
+        let select = move || async move { db.use_ns(NS).use_db(DB_NAME).await.map_err(|e| e.to_string()) };
+
+        // Server not up yet: nothing is recorded, so the next call tries again.
+        let down = init
+            .run(|| async { Err::<(), _>("connection refused".to_string()) }, select)
+            .await;
+        assert!(down.is_err());
+
+        // Connects, but selecting the namespace fails.
+        let half = init.run(connect, || async { Err::<(), _>("use_ns failed".to_string()) }).await;
+        assert!(half.is_err());
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+
+        // The retry skips the connect (the client can't connect twice) and finishes.
+        assert_eq!(init.run(connect, select).await, Ok(()));
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+
+        // Done: later calls run neither step.
+        let never = || async { Err::<(), _>("should not run".to_string()) };
+        assert_eq!(init.run(never, never).await, Ok(()));
+    }
+}
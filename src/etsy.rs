@@ -1,11 +1,16 @@
 //! Etsy API v3 client: OAuth token handling and shop receipts (orders).
 
-use crate::log;
-use chrono::{Duration, TimeZone, Utc};
+use crate::http_util::{is_retryable_status, send_with_retry, FetchError, DEFAULT_MAX_ATTEMPTS};
+use crate::{config, log};
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 
-use crate::model::{MetalType, Order, OrderItem, OrderSource};
+use crate::model::{
+    clamp_future_order_date, country_name, EtsyTokenStatus, MetalType, Order, OrderItem, OrderSource, OrderStatus,
+    ShippingAddress,
+};
 
 fn etsy_keystring() -> String {
     std::env::var("ETSY_KEYSTRING").unwrap_or_default()
@@ -28,41 +33,38 @@ struct EtsyOAuthConfig {
     access_token: Option<String>,
     #[serde(default)]
     expires_at_utc_secs: Option<i64>,
+    /// Shop id discovered from the token when `ETSY_SHOP_ID` is unset.
+    #[serde(default)]
+    shop_id: Option<String>,
+    /// When the current refresh token was saved or last rotated by Etsy.
+    #[serde(default)]
+    refresh_token_issued_utc_secs: Option<i64>,
+    #[serde(default)]
+    last_refresh_ok_utc_secs: Option<i64>,
+    /// Why Etsy rejected the last refresh; cleared by a successful one.
+    #[serde(default)]
+    last_refresh_error: Option<String>,
 }
 
-fn etsy_config_path() -> Option<PathBuf> {
-    // HA add-on: persistent storage at /data/
-    let ha_path = PathBuf::from("/data/etsy_oauth.json");
-    if ha_path.parent().is_some_and(|p| p.exists()) {
-        return Some(ha_path);
-    }
-    // Desktop / local dev: system config directory
-    directories::ProjectDirs::from("com", "KingsOfAlchemy", "OrderTracker")
-        .map(|d| d.config_dir().join("etsy_oauth.json"))
-}
+/// Etsy refresh tokens stop working 90 days after they were issued.
+const REFRESH_TOKEN_LIFETIME_DAYS: i64 = 90;
+/// Start warning this many days before the refresh token runs out.
+const REFRESH_TOKEN_WARN_DAYS: i64 = 10;
+
+const ETSY_CONFIG_FILE: &str = "etsy_oauth.json";
 
 fn load_etsy_config() -> EtsyOAuthConfig {
-    let path = match etsy_config_path() {
-        Some(p) => p,
-        None => return EtsyOAuthConfig::default(),
-    };
-    let Ok(data) = std::fs::read_to_string(&path) else {
-        return EtsyOAuthConfig::default();
-    };
-    serde_json::from_str(&data).unwrap_or_default()
+    config::read_config_file(ETSY_CONFIG_FILE)
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
 }
 
 fn save_etsy_config(cfg: &EtsyOAuthConfig) -> Result<(), String> {
-    let path = etsy_config_path().ok_or_else(|| "No config dir".to_string())?;
-    if let Some(parent) = path.parent() {
-        let _ = std::fs::create_dir_all(parent);
-    }
     let data = serde_json::to_string_pretty(cfg).map_err(|e| e.to_string())?;
-    std::fs::write(&path, data).map_err(|e| e.to_string())?;
-    Ok(())
+    config::write_config_file(ETSY_CONFIG_FILE, &data)
 }
 
-async fn get_etsy_access_token() -> Result<String, String> {
+async fn get_etsy_access_token() -> Result<String, FetchError> {
     let mut cfg = load_etsy_config();
     let now_secs = Utc::now().timestamp();
     let expires = cfg.expires_at_utc_secs.unwrap_or(0);
@@ -77,27 +79,38 @@ async fn get_etsy_access_token() -> Result<String, String> {
     if !secret.is_empty() {
         return Ok(secret);
     }
-    Err("Etsy not connected. Get a refresh token from order-tracker.kingsofalchemy.com/connect and paste it in Settings.".to_string())
+    Err(FetchError::NotConfigured(
+        "Etsy not connected. Get a refresh token from order-tracker.kingsofalchemy.com/connect and paste it in Settings."
+            .to_string(),
+    ))
 }
 
-async fn refresh_etsy_token_async(cfg: &mut EtsyOAuthConfig, refresh_token: &str) -> Result<String, String> {
+async fn refresh_etsy_token_async(cfg: &mut EtsyOAuthConfig, refresh_token: &str) -> Result<String, FetchError> {
     let keystring = etsy_keystring();
     let params = [
         ("grant_type", "refresh_token"),
         ("client_id", keystring.as_str()),
         ("refresh_token", refresh_token),
     ];
-    let res = reqwest::Client::new()
+    let request = reqwest::Client::new()
         .post("https://api.etsy.com/v3/public/oauth/token")
-        .form(&params)
-        .send()
+        .form(&params);
+    let res = send_with_retry(request, DEFAULT_MAX_ATTEMPTS, is_retryable_status)
         .await
-        .map_err(|e| format!("Refresh request failed: {}", e))?;
+        .map_err(|e| FetchError::Network(format!("Refresh request failed: {}", e)))?;
 
     if !res.status().is_success() {
-        let status = res.status();
-        let body = res.text().await.unwrap_or_default();
-        return Err(format!("Etsy token refresh failed: {} - {}", status, body));
+        // Etsy rejects a bad refresh token with a 400 (invalid_grant), which is an auth problem.
+        let err = match FetchError::from_response(res, "Etsy token refresh failed").await {
+            FetchError::Api { context, status, body } if status < 500 => FetchError::Auth { context, status, body },
+            other => other,
+        };
+        // Only a rejected token needs reconnecting; throttling and outages don't count.
+        if let FetchError::Auth { .. } = &err {
+            cfg.last_refresh_error = Some(err.message_with_body());
+            let _ = save_etsy_config(cfg);
+        }
+        return Err(err);
     }
 
     #[derive(Deserialize)]
@@ -106,13 +119,20 @@ async fn refresh_etsy_token_async(cfg: &mut EtsyOAuthConfig, refresh_token: &str
         expires_in: Option<u64>,
         refresh_token: Option<String>,
     }
-    let tok: TokenResponse = res.json().await.map_err(|e| format!("Parse token response: {}", e))?;
+    let tok: TokenResponse = res
+        .json()
+        .await
+        .map_err(|e| FetchError::Parse(format!("Parse token response: {}", e)))?;
     let expires_in = tok.expires_in.unwrap_or(3600);
+    let now_secs = Utc::now().timestamp();
     cfg.access_token = Some(tok.access_token.clone());
-    cfg.expires_at_utc_secs = Some(Utc::now().timestamp() + expires_in as i64);
+    cfg.expires_at_utc_secs = Some(now_secs + expires_in as i64);
     if let Some(rt) = tok.refresh_token {
         cfg.refresh_token = Some(rt);
+        cfg.refresh_token_issued_utc_secs = Some(now_secs);
     }
+    cfg.last_refresh_ok_utc_secs = Some(now_secs);
+    cfg.last_refresh_error = None;
     let _ = save_etsy_config(cfg);
     Ok(tok.access_token)
 }
@@ -123,9 +143,153 @@ pub fn save_etsy_refresh_token(refresh_token: String) -> Result<(), String> {
     cfg.refresh_token = Some(refresh_token.trim().to_string());
     cfg.access_token = None;
     cfg.expires_at_utc_secs = None;
+    // A new token may belong to a different account; rediscover the shop.
+    cfg.shop_id = None;
+    cfg.refresh_token_issued_utc_secs = Some(Utc::now().timestamp());
+    cfg.last_refresh_error = None;
     save_etsy_config(&cfg)
 }
 
+/// Token health from the stored config, without calling Etsy.
+pub fn etsy_token_status() -> EtsyTokenStatus {
+    let cfg = load_etsy_config();
+    if cfg.refresh_token.as_deref().is_none_or(|t| t.trim().is_empty()) {
+        return EtsyTokenStatus::NotConnected;
+    }
+    if let Some(reason) = cfg.last_refresh_error {
+        return EtsyTokenStatus::NeedsReconnect { reason };
+    }
+    let Some(issued) = cfg.refresh_token_issued_utc_secs.or(cfg.last_refresh_ok_utc_secs) else {
+        // Saved before issue dates were tracked; the next refresh records one.
+        return EtsyTokenStatus::Connected;
+    };
+    let age_days = (Utc::now().timestamp() - issued) / 86_400;
+    let days_left = REFRESH_TOKEN_LIFETIME_DAYS - age_days;
+    if days_left <= 0 {
+        EtsyTokenStatus::NeedsReconnect {
+            reason: format!("Refresh token is over {} days old", REFRESH_TOKEN_LIFETIME_DAYS),
+        }
+    } else if days_left <= REFRESH_TOKEN_WARN_DAYS {
+        EtsyTokenStatus::ExpiringSoon { days_left }
+    } else {
+        EtsyTokenStatus::Connected
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Shop id discovery (when ETSY_SHOP_ID is unset)
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+struct EtsyMe {
+    user_id: i64,
+    #[serde(default)]
+    shop_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EtsyShop {
+    shop_id: i64,
+    #[serde(default)]
+    shop_name: Option<String>,
+}
+
+/// `/users/{id}/shops` returns a single shop object, but paginated `{count, results}`
+/// shapes are accepted too so multi-shop tokens are detected instead of guessed.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EtsyShopsResponse {
+    Many { results: Vec<EtsyShop> },
+    One(EtsyShop),
+}
+
+/// Shop id to fetch receipts for: `ETSY_SHOP_ID`, else a cached discovery, else ask Etsy
+/// which shop the token belongs to (and cache the answer).
+async fn resolve_etsy_shop_id(
+    client: &reqwest::Client,
+    access_token: &str,
+    x_api_key: &str,
+) -> Result<String, FetchError> {
+    let configured = etsy_shop_id();
+    if !configured.trim().is_empty() {
+        return Ok(configured.trim().to_string());
+    }
+    let mut cfg = load_etsy_config();
+    if let Some(id) = cfg.shop_id.clone().filter(|id| !id.is_empty()) {
+        return Ok(id);
+    }
+
+    log::app_log("INFO", "Etsy: ETSY_SHOP_ID not set, discovering shop from token...");
+    let get = |url: String| {
+        client
+            .get(url)
+            .header("x-api-key", x_api_key)
+            .header("Authorization", format!("Bearer {}", access_token))
+    };
+
+    let res = send_with_retry(
+        get("https://api.etsy.com/v3/application/users/me".to_string()),
+        DEFAULT_MAX_ATTEMPTS,
+        is_retryable_status,
+    )
+    .await
+    .map_err(|e| FetchError::Network(format!("Etsy users/me request failed: {}", e)))?;
+    if !res.status().is_success() {
+        return Err(FetchError::from_response(res, "Etsy API error").await);
+    }
+    let me: EtsyMe = res
+        .json()
+        .await
+        .map_err(|e| FetchError::Parse(format!("Parse users/me: {}", e)))?;
+
+    let shop_id = match me.shop_id {
+        Some(id) => id,
+        None => {
+            let res = send_with_retry(
+                get(format!("https://api.etsy.com/v3/application/users/{}/shops", me.user_id)),
+                DEFAULT_MAX_ATTEMPTS,
+                is_retryable_status,
+            )
+            .await
+            .map_err(|e| FetchError::Network(format!("Etsy shops request failed: {}", e)))?;
+            if !res.status().is_success() {
+                return Err(FetchError::from_response(res, "Etsy API error").await);
+            }
+            let shops: EtsyShopsResponse = res
+                .json()
+                .await
+                .map_err(|e| FetchError::Parse(format!("Parse shops: {}", e)))?;
+            match shops {
+                EtsyShopsResponse::One(shop) => shop.shop_id,
+                EtsyShopsResponse::Many { results } => match results.as_slice() {
+                    [] => {
+                        return Err(FetchError::NotConfigured(
+                            "Etsy account has no shop. Set ETSY_SHOP_ID.".to_string(),
+                        ));
+                    }
+                    [shop] => shop.shop_id,
+                    shops => {
+                        let options: Vec<String> = shops
+                            .iter()
+                            .map(|s| format!("{} ({})", s.shop_id, s.shop_name.as_deref().unwrap_or("unnamed")))
+                            .collect();
+                        return Err(FetchError::NotConfigured(format!(
+                            "Etsy token has access to multiple shops: {}. Set ETSY_SHOP_ID to pick one.",
+                            options.join(", ")
+                        )));
+                    }
+                },
+            }
+        }
+    };
+
+    let shop_id = shop_id.to_string();
+    log::app_log("INFO", format!("Etsy: discovered shop id {}", shop_id));
+    cfg.shop_id = Some(shop_id.clone());
+    let _ = save_etsy_config(&cfg);
+    Ok(shop_id)
+}
+
 // ---------------------------------------------------------------------------
 // Etsy API response types (v3 shop receipts)
 // ---------------------------------------------------------------------------
@@ -144,12 +308,34 @@ struct EtsyReceipt {
     name: String,
     #[serde(rename = "created_timestamp")]
     create_timestamp: i64,
-    #[serde(alias = "total", default)]
+    /// What the buyer paid: items + shipping + tax - discounts. This is the order total.
+    #[serde(default)]
     grandtotal: Option<EtsyMoney>,
+    /// Items after discounts, before shipping and tax.
+    #[serde(default)]
+    subtotal: Option<EtsyMoney>,
+    #[serde(default)]
+    total_shipping_cost: Option<EtsyMoney>,
+    #[serde(default)]
+    total_tax_cost: Option<EtsyMoney>,
     transactions: Option<Vec<EtsyTransaction>>,
     first_line: Option<String>,
+    #[serde(default)]
+    second_line: Option<String>,
+    #[serde(default)]
+    city: Option<String>,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    zip: Option<String>,
+    #[serde(default)]
+    country_iso: Option<String>,
     formatted_address: Option<String>,
     status: Option<String>,
+    #[serde(default)]
+    is_paid: Option<bool>,
+    #[serde(default)]
+    is_shipped: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -159,6 +345,14 @@ struct EtsyMoney {
     currency_code: Option<String>,
 }
 
+impl EtsyMoney {
+    /// Amount in major units (`amount / divisor`, divisor defaulting to 100).
+    fn to_f64(&self) -> f64 {
+        let divisor = self.divisor.unwrap_or(100).max(1) as f64;
+        (self.amount.unwrap_or(0) as f64) / divisor
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct EtsyTransaction {
     title: Option<String>,
@@ -185,24 +379,76 @@ struct EtsyListingImage {
     url_170x135: Option<String>,
 }
 
+/// Most listing images kept across refreshes; the least recently used is dropped past this.
+const IMAGE_CACHE_CAPACITY: usize = 1000;
+
+/// Listing image URLs by `(listing_id, image_id)`, shared by every refresh while the server runs.
+/// Each entry carries the tick it was last used at, for LRU eviction.
+#[derive(Default)]
+struct ImageUrlCache {
+    entries: HashMap<(i64, i64), (String, u64)>,
+    tick: u64,
+}
+
+impl ImageUrlCache {
+    fn get(&mut self, key: (i64, i64)) -> Option<String> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(&key).map(|(url, used)| {
+            *used = tick;
+            url.clone()
+        })
+    }
+
+    fn insert(&mut self, key: (i64, i64), url: String) {
+        self.tick += 1;
+        self.entries.insert(key, (url, self.tick));
+        if self.entries.len() > IMAGE_CACHE_CAPACITY {
+            let oldest = self.entries.iter().min_by_key(|(_, (_, used))| *used).map(|(k, _)| *k);
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+static IMAGE_URL_CACHE: OnceLock<Mutex<ImageUrlCache>> = OnceLock::new();
+
+fn image_url_cache() -> &'static Mutex<ImageUrlCache> {
+    IMAGE_URL_CACHE.get_or_init(|| Mutex::new(ImageUrlCache::default()))
+}
+
+/// Image URLs for `keys`, served from [IMAGE_URL_CACHE] where possible; only misses hit the API.
 async fn fetch_listing_image_urls(
     client: &reqwest::Client,
     access_token: &str,
     x_api_key: &str,
     keys: &[(i64, i64)],
-) -> std::collections::HashMap<(i64, i64), String> {
-    let mut out = std::collections::HashMap::new();
-    for &(listing_id, image_id) in keys {
+) -> HashMap<(i64, i64), String> {
+    let mut out = HashMap::new();
+    let mut missing = Vec::new();
+    if let Ok(mut cache) = image_url_cache().lock() {
+        for &key in keys {
+            match cache.get(key) {
+                Some(url) => {
+                    out.insert(key, url);
+                }
+                None => missing.push(key),
+            }
+        }
+    } else {
+        missing.extend_from_slice(keys);
+    }
+    for (listing_id, image_id) in missing {
         let url = format!(
             "https://api.etsy.com/v3/application/listings/{}/images/{}",
             listing_id, image_id
         );
-        let resp = client
+        let request = client
             .get(&url)
             .header("x-api-key", x_api_key)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .send()
-            .await;
+            .header("Authorization", format!("Bearer {}", access_token));
+        let resp = send_with_retry(request, DEFAULT_MAX_ATTEMPTS, is_retryable_status).await;
         if let Ok(r) = resp {
             if r.status().is_success() {
                 if let Ok(img) = r.json::<EtsyListingImage>().await {
@@ -211,6 +457,9 @@ async fn fetch_listing_image_urls(
                         .or(img.url_75x75)
                         .filter(|s| !s.is_empty());
                     if let Some(u) = u {
+                        if let Ok(mut cache) = image_url_cache().lock() {
+                            cache.insert((listing_id, image_id), u.clone());
+                        }
                         out.insert((listing_id, image_id), u);
                     }
                 }
@@ -220,59 +469,327 @@ async fn fetch_listing_image_urls(
     out
 }
 
+/// Ring size from Etsy variations (`(formatted_name, formatted_value)` pairs). Prefers a
+/// variation named exactly "Ring Size" or "Size", then any name mentioning both "ring" and
+/// "size"; either way the value must look like a ring size. "Chain Size" / "Pendant Size"
+/// and values like "18 inches" are ignored.
+fn extract_ring_size(variations: &[(String, String)]) -> Option<String> {
+    let plausible = |(_, value): &&(String, String)| is_plausible_ring_size(value);
+    let exact = variations.iter().filter(plausible).find(|(name, _)| {
+        let name = name.trim().to_lowercase();
+        name == "ring size" || name == "size"
+    });
+    // "Ring size (US)", "Choose your ring size"; "ring" must be a whole word so
+    // "Earring Size" doesn't count.
+    let loose = || {
+        variations.iter().filter(plausible).find(|(name, _)| {
+            let name = name.to_lowercase();
+            let words: Vec<&str> = name.split(|c: char| !c.is_alphanumeric()).collect();
+            words.contains(&"ring") && words.contains(&"size")
+        })
+    };
+    exact.or_else(loose).map(|(_, value)| value.trim().to_string())
+}
+
+/// US/Canada ring sizes run roughly 1-16 in quarter/half steps ("7", "7.5", "7 1/2", "US 7").
+/// Anything with a unit (inches, cm, mm) is a chain or bracelet length, not a ring size.
+fn is_plausible_ring_size(value: &str) -> bool {
+    let lower = value.trim().to_lowercase();
+    if ["inch", "\"", "cm", "mm"].iter().any(|unit| lower.contains(unit)) {
+        return false;
+    }
+    let mut rest = lower.as_str();
+    for prefix in ["us", "size"] {
+        rest = rest.trim_start().trim_start_matches(prefix);
+    }
+    let number: String = rest
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    number.parse::<f64>().is_ok_and(|n| (1.0..=16.0).contains(&n))
+}
+
+/// Map Etsy's receipt status (Open, Paid, Completed, Canceled, Fully Refunded, ...) to
+/// the shared status plus a Shopify-style payment state, so both sources label and color
+/// the same way. An absent status means an open receipt.
+fn map_etsy_status(
+    status: Option<&str>,
+    is_paid: Option<bool>,
+    is_shipped: Option<bool>,
+) -> (OrderStatus, Option<String>) {
+    let shipped_status = if is_shipped == Some(true) {
+        OrderStatus::Fulfilled
+    } else {
+        OrderStatus::Unfulfilled
+    };
+    let (status, financial) = match status.map(|s| s.trim().to_lowercase()).as_deref() {
+        None | Some("open") => {
+            let financial = if is_paid == Some(true) { "paid" } else { "pending" };
+            (shipped_status, Some(financial))
+        }
+        Some("payment processing") => (OrderStatus::Unfulfilled, Some("pending")),
+        Some("paid") => (shipped_status, Some("paid")),
+        Some("completed") => (OrderStatus::Fulfilled, Some("paid")),
+        Some("canceled") | Some("cancelled") => (OrderStatus::Cancelled, None),
+        Some("fully refunded") => (OrderStatus::Refunded, Some("refunded")),
+        Some("partially refunded") => (shipped_status, Some("partially_refunded")),
+        Some(_) => (OrderStatus::Other, None),
+    };
+    (status, financial.map(str::to_string))
+}
+
+/// Etsy timestamps are epoch seconds, but some payloads use milliseconds; detect by magnitude.
+fn etsy_timestamp(ts: i64) -> Option<DateTime<Utc>> {
+    if ts > 1_000_000_000_000 {
+        Utc.timestamp_millis_opt(ts).single()
+    } else {
+        Utc.timestamp_opt(ts, 0).single()
+    }
+}
+
+/// Map one Etsy receipt into the shared [Order] shape. Pure (no I/O). `images` is keyed
+/// by `(listing_id, listing_image_id)`. Returns `None` (logged) for a malformed receipt
+/// with no usable creation time, since its due date and place in the queue are unknown.
+fn map_etsy_receipt(r: EtsyReceipt, images: &HashMap<(i64, i64), String>) -> Option<Order> {
+    let order_date = etsy_timestamp(r.create_timestamp).filter(|_| r.create_timestamp > 0);
+    let Some(order_date) = order_date else {
+        log::app_log(
+            "WARN",
+            format!(
+                "Etsy: skipping receipt {} with unusable create_timestamp {}",
+                r.receipt_id, r.create_timestamp
+            ),
+        );
+        return None;
+    };
+    let (order_date, raw_order_date) = clamp_future_order_date(order_date, Utc::now());
+    if let Some(raw) = raw_order_date {
+        log::app_log(
+            "WARN",
+            format!(
+                "Etsy: receipt #{} has a future created_timestamp ({}); using now for due date",
+                r.order_id.unwrap_or(r.receipt_id),
+                raw.to_rfc3339()
+            ),
+        );
+    }
+    let due_date = r
+        .transactions
+        .as_deref()
+        .unwrap_or(&[])
+        .iter()
+        .filter_map(|t| t.expected_ship_date)
+        .max()
+        .and_then(etsy_timestamp)
+        .unwrap_or_else(|| order_date + Duration::days(14));
+
+    let currency = [&r.grandtotal, &r.subtotal, &r.total_shipping_cost, &r.total_tax_cost]
+        .into_iter()
+        .flatten()
+        .find_map(|m| m.currency_code.clone())
+        .unwrap_or_else(|| "USD".to_string());
+
+    let items: Vec<OrderItem> = r
+        .transactions
+        .unwrap_or_default()
+        .into_iter()
+        .map(|t| {
+            let title = t.title.unwrap_or_else(|| "Item".to_string());
+            let qty = t.quantity.unwrap_or(1);
+            let price_val = t.price.as_ref().map(EtsyMoney::to_f64).unwrap_or(0.0);
+            let variations: Vec<(String, String)> = t
+                .variations
+                .unwrap_or_default()
+                .into_iter()
+                .map(|v| (v.formatted_name.unwrap_or_default(), v.formatted_value.unwrap_or_default()))
+                .filter(|(n, val)| !(n.is_empty() && val.is_empty()))
+                .collect();
+            let variant_parts: Vec<String> = variations
+                .iter()
+                .map(|(n, val)| format!("{}: {}", n, val))
+                .collect();
+            let variant_info = if variant_parts.is_empty() {
+                None
+            } else {
+                Some(variant_parts.join(", "))
+            };
+            let full_name = format!("{} {}", &title, variant_info.as_deref().unwrap_or(""));
+            let metals = MetalType::detect_all(&full_name);
+            let metal_type = metals.first().cloned().unwrap_or(MetalType::Unknown);
+            let ring_size = extract_ring_size(&variations);
+
+            let image_url = t
+                .listing_id
+                .zip(t.listing_image_id)
+                .and_then(|k| images.get(&k).cloned());
+            OrderItem {
+                name: title,
+                quantity: qty as u32,
+                price: price_val,
+                metal_type,
+                ring_size,
+                variant_info,
+                image_url,
+                metals,
+                location: None,
+                fulfilled: None,
+            }
+        })
+        .collect();
+
+    // `grandtotal` already includes shipping and tax. Without it, rebuild the same figure
+    // from the subtotal (or the items) plus shipping and tax.
+    let total_price = match &r.grandtotal {
+        Some(grandtotal) => grandtotal.to_f64(),
+        None => {
+            let subtotal = r
+                .subtotal
+                .as_ref()
+                .map(EtsyMoney::to_f64)
+                .unwrap_or_else(|| items.iter().map(|i| i.price * i.quantity as f64).sum::<f64>());
+            let extras = [&r.total_shipping_cost, &r.total_tax_cost]
+                .into_iter()
+                .flatten()
+                .map(EtsyMoney::to_f64)
+                .sum::<f64>();
+            subtotal + extras
+        }
+    };
+
+    // Structured parts when Etsy sends them; otherwise the preformatted (multi-line) address.
+    let shipping_address = if r.first_line.is_some() {
+        Some(ShippingAddress {
+            name: Some(r.name.trim().to_string()).filter(|n| !n.is_empty()),
+            line1: r.first_line.clone(),
+            line2: r.second_line.clone(),
+            city: r.city.clone(),
+            region: r.state.clone(),
+            postal_code: r.zip.clone(),
+            // Etsy only sends the ISO code; keep both so either can be searched.
+            country: r
+                .country_iso
+                .as_deref()
+                .map(|iso| country_name(iso).map_or_else(|| iso.to_string(), str::to_string)),
+            country_code: r.country_iso.clone(),
+        })
+    } else {
+        r.formatted_address.clone().map(|formatted| ShippingAddress {
+            line1: Some(formatted),
+            ..Default::default()
+        })
+    };
+    let (status, financial_status) = map_etsy_status(r.status.as_deref(), r.is_paid, r.is_shipped);
+
+    Some(Order {
+        id: r.receipt_id.to_string(),
+        source: OrderSource::Etsy,
+        order_number: format!("#{}", r.order_id.unwrap_or(r.receipt_id)),
+        customer_name: {
+            let n = r.name.trim().to_string();
+            if n.is_empty() {
+                "Unknown".to_string()
+            } else {
+                n
+            }
+        },
+        items,
+        order_date,
+        due_date,
+        total_price,
+        currency,
+        status,
+        financial_status,
+        shipping_address,
+        computed_due_date: None,
+        location: None,
+        raw_order_date,
+        is_test: false,
+        admin_url: Some(format!("https://www.etsy.com/your/orders/sold?order_id={}", r.receipt_id)),
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Public API
 // ---------------------------------------------------------------------------
 
-/// Fetch shop receipts (orders) from Etsy API v3 (last 60 days). Only paid, not-yet-shipped.
-pub async fn fetch_etsy_orders() -> Result<Vec<Order>, String> {
+/// Shown for a failed receipts request that came back without a body.
+const RECEIPTS_AUTH_HINT: &str = "Check x-api-key and OAuth token (transactions_r scope)";
+
+/// How a [fetch_etsy_orders] error reads in the UI: status and body, since Etsy's JSON body
+/// names the actual problem (e.g. a missing scope).
+pub fn fetch_error_message(e: &FetchError) -> String {
+    e.message_with_body()
+}
+
+/// Fetch paid shop receipts (orders) from Etsy API v3 created in the last `lookback_days`.
+/// Shipped receipts are only included when `include_shipped` is set.
+pub async fn fetch_etsy_orders(lookback_days: i64, include_shipped: bool) -> Result<Vec<Order>, FetchError> {
+    if etsy_keystring().trim().is_empty() {
+        return Err(FetchError::NotConfigured("Etsy not configured: set ETSY_KEYSTRING".to_string()));
+    }
     log::app_log("INFO", "Etsy: getting access token...");
     let access_token = get_etsy_access_token().await?;
     log::app_log("INFO", "Etsy: token OK, requesting receipts...");
     let client = reqwest::Client::new();
     const LIMIT: i32 = 100;
+    let x_api_key = format!("{}:{}", etsy_keystring(), etsy_secret());
+    let shop_id = resolve_etsy_shop_id(&client, &access_token, &x_api_key).await?;
     let base_url = format!(
         "https://api.etsy.com/v3/application/shops/{}/receipts",
-        etsy_shop_id()
+        shop_id
     );
-    let x_api_key = format!("{}:{}", etsy_keystring(), etsy_secret());
 
     let mut all_receipts = Vec::new();
     let mut offset = 0i32;
 
     let was_paid = true;
-    let was_shipped = false;
-    log::app_log("INFO", format!("Etsy: fetching receipts (was_paid={}, was_shipped={})", was_paid, was_shipped));
+    // Omitting was_shipped returns both shipped and unshipped receipts.
+    let shipped_filter = if include_shipped { "" } else { "&was_shipped=false" };
+    // Etsy filters by creation time server-side (epoch seconds), so only in-window pages are fetched.
+    let min_created = (Utc::now() - Duration::days(lookback_days)).timestamp();
+    log::app_log(
+        "INFO",
+        format!(
+            "Etsy: fetching receipts (was_paid={}, include_shipped={}, min_created={})",
+            was_paid, include_shipped, min_created
+        ),
+    );
 
     loop {
         let url = format!(
-            "{}?limit={}&offset={}&was_paid={}&was_shipped={}",
-            base_url, LIMIT, offset, was_paid, was_shipped
+            "{}?limit={}&offset={}&was_paid={}&min_created={}{}",
+            base_url, LIMIT, offset, was_paid, min_created, shipped_filter
         );
         log::app_log("INFO", format!("Etsy: GET receipts offset={}", offset));
-        let response = client
+        let request = client
             .get(&url)
             .header("x-api-key", &x_api_key)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .send()
+            .header("Authorization", format!("Bearer {}", access_token));
+        let response = send_with_retry(request, DEFAULT_MAX_ATTEMPTS, is_retryable_status)
             .await
-            .map_err(|e| format!("Etsy request failed: {}", e))?;
+            .map_err(|e| FetchError::Network(format!("Etsy request failed: {}", e)))?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(format!(
-                "Etsy API error: {} - {}",
-                status,
-                if body.is_empty() {
-                    "Check x-api-key and OAuth token (transactions_r scope)".to_string()
-                } else {
-                    body
-                }
-            ));
+            return Err(match FetchError::from_response(response, "Etsy API error").await {
+                FetchError::Api { context, status, body } if body.is_empty() => FetchError::Api {
+                    context,
+                    status,
+                    body: RECEIPTS_AUTH_HINT.to_string(),
+                },
+                FetchError::Auth { context, status, body } if body.is_empty() => FetchError::Auth {
+                    context,
+                    status,
+                    body: RECEIPTS_AUTH_HINT.to_string(),
+                },
+                other => other,
+            });
         }
 
-        let raw_body = response.text().await.map_err(|e| format!("Etsy response read failed: {}", e))?;
+        let raw_body = response
+            .text()
+            .await
+            .map_err(|e| FetchError::Network(format!("Etsy response read failed: {}", e)))?;
         let page: EtsyReceiptsResponse = match serde_json::from_str(&raw_body) {
             Ok(p) => p,
             Err(e) => {
@@ -282,7 +799,10 @@ pub async fn fetch_etsy_orders() -> Result<Vec<Order>, String> {
                     raw_body.clone()
                 };
                 log::app_log("ERROR", format!("Etsy parse (offset={}): {}", offset, preview));
-                return Err(format!("Etsy response parse failed: {} | raw preview: {}", e, preview));
+                return Err(FetchError::Parse(format!(
+                    "Etsy response parse failed: {} | raw preview: {}",
+                    e, preview
+                )));
             }
         };
 
@@ -309,7 +829,7 @@ pub async fn fetch_etsy_orders() -> Result<Vec<Order>, String> {
     }
     image_keys.sort_unstable();
     image_keys.dedup();
-    let image_urls: std::collections::HashMap<(i64, i64), String> = fetch_listing_image_urls(
+    let image_urls: HashMap<(i64, i64), String> = fetch_listing_image_urls(
         &client,
         &access_token,
         &x_api_key,
@@ -319,137 +839,170 @@ pub async fn fetch_etsy_orders() -> Result<Vec<Order>, String> {
 
     log::app_log("INFO", format!("Etsy: got {} image URLs, mapping to orders...", image_urls.len()));
 
-    let two_months_ago = Utc::now() - Duration::days(60);
     let orders: Vec<Order> = all_receipts
         .into_iter()
-        .filter_map(|r| {
-            let order_ts = r.create_timestamp;
-            let order_date = if order_ts > 1_000_000_000_000 {
-                Utc.timestamp_millis_opt(order_ts).single().unwrap_or(Utc::now())
-            } else {
-                Utc.timestamp_opt(order_ts, 0).single().unwrap_or(Utc::now())
-            };
-            if order_date < two_months_ago {
-                return None;
-            }
-            let due_date = r
-                .transactions
-                .as_deref()
-                .unwrap_or(&[])
-                .iter()
-                .filter_map(|t| t.expected_ship_date)
-                .max()
-                .and_then(|ts| {
-                    if ts > 1_000_000_000_000 {
-                        Utc.timestamp_millis_opt(ts).single()
-                    } else {
-                        Utc.timestamp_opt(ts, 0).single()
-                    }
-                })
-                .unwrap_or_else(|| order_date + Duration::days(14));
-
-            let (total_price, currency) = if let Some(ref total_money) = r.grandtotal {
-                let divisor = total_money.divisor.unwrap_or(100).max(1) as f64;
-                let price = (total_money.amount.unwrap_or(0) as f64) / divisor;
-                let curr = total_money
-                    .currency_code
-                    .clone()
-                    .unwrap_or_else(|| "USD".to_string());
-                (price, curr)
-            } else {
-                (0.0, "USD".to_string())
-            };
-
-            let items: Vec<OrderItem> = r
-                .transactions
-                .unwrap_or_default()
-                .into_iter()
-                .map(|t| {
-                    let title = t.title.unwrap_or_else(|| "Item".to_string());
-                    let qty = t.quantity.unwrap_or(1);
-                    let price_val = t
-                        .price
-                        .as_ref()
-                        .map(|p| {
-                            let div = p.divisor.unwrap_or(100).max(1) as f64;
-                            (p.amount.unwrap_or(0) as f64) / div
-                        })
-                        .unwrap_or(0.0);
-                    let variant_parts: Vec<String> = t
-                        .variations
-                        .unwrap_or_default()
-                        .into_iter()
-                        .filter_map(|v| {
-                            let n = v.formatted_name.unwrap_or_default();
-                            let val = v.formatted_value.unwrap_or_default();
-                            if n.is_empty() && val.is_empty() {
-                                None
-                            } else {
-                                Some(format!("{}: {}", n, val))
-                            }
-                        })
-                        .collect();
-                    let variant_info = if variant_parts.is_empty() {
-                        None
-                    } else {
-                        Some(variant_parts.join(", "))
-                    };
-                    let full_name = format!("{} {}", &title, variant_info.as_deref().unwrap_or(""));
-                    let metal_type = MetalType::from_string(&full_name);
-                    let ring_size = variant_parts
-                        .iter()
-                        .find(|s| {
-                            s.to_lowercase().contains("ring") || s.to_lowercase().contains("size")
-                        })
-                        .cloned();
-
-                    let image_url = t
-                        .listing_id
-                        .zip(t.listing_image_id)
-                        .and_then(|k| image_urls.get(&k).cloned());
-                    OrderItem {
-                        name: title,
-                        quantity: qty as u32,
-                        price: price_val,
-                        metal_type,
-                        ring_size,
-                        variant_info,
-                        image_url,
-                    }
-                })
-                .collect();
-
-            let total_price = if total_price > 0.0 {
-                total_price
-            } else {
-                items.iter().map(|i| i.price * i.quantity as f64).sum::<f64>()
-            };
-
-            let shipping_address = r.first_line.clone().or(r.formatted_address.clone());
-
-            Some(Order {
-                id: r.receipt_id.to_string(),
-                source: OrderSource::Etsy,
-                order_number: format!("#{}", r.order_id.unwrap_or(r.receipt_id)),
-                customer_name: {
-                    let n = r.name.trim().to_string();
-                    if n.is_empty() {
-                        "Unknown".to_string()
-                    } else {
-                        n
-                    }
-                },
-                items,
-                order_date,
-                due_date,
-                total_price,
-                currency,
-                status: r.status.unwrap_or_else(|| "open".to_string()),
-                shipping_address,
-            })
-        })
+        .filter_map(|r| map_etsy_receipt(r, &image_urls))
         .collect();
 
     log::app_log("INFO", format!("Etsy: built {} orders", orders.len()));
     Ok(orders)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-01T15:00:00Z in epoch seconds.
+    const PLACED_SECS: i64 = 1_709_305_200;
+
+    /// A one-transaction receipt, with `overrides` replacing top-level fields.
+    fn receipt_fixture(overrides: serde_json::Value) -> EtsyReceipt {
+        let mut receipt = serde_json::json!({
+            "receipt_id": 9001,
+            "order_id": 3001,
+            "name": "Grace Hopper",
+            "created_timestamp": PLACED_SECS,
+            "transactions": [{
+                "title": "Gold Signet Ring",
+                "quantity": 2,
+                "price": { "amount": 12500, "divisor": 100, "currency_code": "USD" },
+                "variations": [{ "formatted_name": "Ring Size", "formatted_value": "7" }]
+            }],
+            "status": "Paid"
+        });
+        if let (Some(base), Some(extra)) = (receipt.as_object_mut(), overrides.as_object()) {
+            base.extend(extra.clone());
+        }
+        serde_json::from_value(receipt).expect("fixture should deserialize")
+    }
+
+    fn map(overrides: serde_json::Value) -> Order {
+        map_etsy_receipt(receipt_fixture(overrides), &HashMap::new()).expect("receipt maps")
+    }
+
+    #[test]
+    fn money_uses_divisor() {
+        let order = map(serde_json::json!({
+            "transactions": [{
+                "title": "Silver Band",
+                "quantity": 1,
+                "price": { "amount": 12345, "divisor": 1000, "currency_code": "USD" }
+            }],
+            "grandtotal": { "amount": 20500, "divisor": 1000, "currency_code": "USD" }
+        }));
+        assert_eq!(order.items[0].price, 12.345);
+        assert_eq!(order.total_price, 20.5);
+    }
+
+    #[test]
+    fn timestamp_in_seconds_or_millis() {
+        let placed = Utc.timestamp_opt(PLACED_SECS, 0).unwrap();
+        let from_secs = map(serde_json::json!({}));
+        let from_millis = map(serde_json::json!({ "created_timestamp": PLACED_SECS * 1000 }));
+        assert_eq!(from_secs.order_date, placed);
+        assert_eq!(from_millis.order_date, placed);
+        assert_eq!(from_millis.due_date, placed + Duration::days(14));
+    }
+
+    #[test]
+    fn receipt_without_created_time_is_skipped() {
+        let receipt = receipt_fixture(serde_json::json!({ "created_timestamp": 0 }));
+        assert_eq!(map_etsy_receipt(receipt, &HashMap::new()), None);
+    }
+
+    #[test]
+    fn missing_total_sums_items() {
+        let order = map(serde_json::json!({}));
+        assert_eq!(order.total_price, 250.0);
+        assert_eq!(order.currency, "USD");
+    }
+
+    #[test]
+    fn grandtotal_includes_shipping() {
+        let order = map(serde_json::json!({
+            "grandtotal": { "amount": 27300, "divisor": 100, "currency_code": "USD" },
+            "subtotal": { "amount": 25000, "divisor": 100, "currency_code": "USD" },
+            "total_shipping_cost": { "amount": 1500, "divisor": 100, "currency_code": "USD" },
+            "total_tax_cost": { "amount": 800, "divisor": 100, "currency_code": "USD" }
+        }));
+        assert_eq!(order.total_price, 273.0);
+    }
+
+    #[test]
+    fn missing_grandtotal_adds_shipping_and_tax() {
+        let order = map(serde_json::json!({
+            "subtotal": { "amount": 22500, "divisor": 100, "currency_code": "CAD" },
+            "total_shipping_cost": { "amount": 1500, "divisor": 100, "currency_code": "CAD" },
+            "total_tax_cost": { "amount": 800, "divisor": 100, "currency_code": "CAD" }
+        }));
+        assert_eq!(order.total_price, 248.0);
+        assert_eq!(order.currency, "CAD");
+
+        // Without a subtotal either, the items stand in for it.
+        let order = map(serde_json::json!({
+            "total_shipping_cost": { "amount": 1500, "divisor": 100, "currency_code": "USD" }
+        }));
+        assert_eq!(order.total_price, 265.0);
+    }
+
+    fn variations(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn chain_and_pendant_sizes_are_not_ring_sizes() {
+        assert_eq!(extract_ring_size(&variations(&[("Chain Size", "18 in")])), None);
+        assert_eq!(extract_ring_size(&variations(&[("Chain Size", "20\"")])), None);
+        assert_eq!(extract_ring_size(&variations(&[("Pendant Size", "Large")])), None);
+        assert_eq!(extract_ring_size(&variations(&[("Pendant Size", "12mm")])), None);
+        assert_eq!(extract_ring_size(&variations(&[("Size", "18 inches")])), None);
+        assert_eq!(extract_ring_size(&variations(&[("Earring Size", "8")])), None);
+        assert_eq!(extract_ring_size(&variations(&[("Earring size/length", "12")])), None);
+    }
+
+    #[test]
+    fn ring_sizes_are_found() {
+        assert_eq!(extract_ring_size(&variations(&[("Ring Size", "7 1/2")])).as_deref(), Some("7 1/2"));
+        assert_eq!(extract_ring_size(&variations(&[("Size", "US 8")])).as_deref(), Some("US 8"));
+        assert_eq!(
+            extract_ring_size(&variations(&[("Chain Size", "18 in"), ("Ring size (US)", " 6.5 ")])).as_deref(),
+            Some("6.5")
+        );
+    }
+
+    #[test]
+    fn maps_receipt_fields() {
+        let order = map(serde_json::json!({}));
+        assert_eq!(order.id, "9001");
+        assert_eq!(order.order_number, "#3001");
+        assert_eq!(order.customer_name, "Grace Hopper");
+        assert_eq!(order.status, OrderStatus::Unfulfilled);
+        assert_eq!(order.financial_status.as_deref(), Some("paid"));
+        let item = &order.items[0];
+        assert_eq!(item.quantity, 2);
+        assert_eq!(item.price, 125.0);
+        assert_eq!(item.metal_type, MetalType::Gold);
+        assert_eq!(item.ring_size.as_deref(), Some("7"));
+        assert_eq!(item.variant_info.as_deref(), Some("Ring Size: 7"));
+    }
+
+    #[test]
+    fn country_iso_is_searchable_by_name_and_code() {
+        let order = map(serde_json::json!({
+            "first_line": "1 Rue Sainte-Catherine",
+            "city": "Montreal",
+            "country_iso": "CA",
+        }));
+        let address = order.shipping_address.expect("structured address");
+        assert_eq!(address.country.as_deref(), Some("Canada"));
+        assert!(address.matches_search("canada"));
+
+        // No "de" anywhere in the address text, so only the ISO code can match.
+        let order = map(serde_json::json!({ "first_line": "Torstrasse 1", "city": "Berlin", "country_iso": "DE" }));
+        let address = order.shipping_address.expect("structured address");
+        assert_eq!(address.country.as_deref(), Some("Germany"));
+        assert!(!address.single_line().to_lowercase().contains("de"));
+        assert!(address.matches_search("de"));
+        assert!(!address.matches_search("us"));
+    }
+}
@@ -0,0 +1,315 @@
+//! Shared HTTP helpers for the Shopify/Etsy clients (server-only): retry with backoff and
+//! a typed error for failed calls.
+
+use std::time::Duration;
+
+use crate::log;
+use reqwest::{RequestBuilder, Response, StatusCode};
+
+/// Attempts used by the API clients (first try + retries).
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 4;
+
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Why a Shopify/Etsy call failed, so callers can tell auth problems from outages.
+/// `Display` is for logs; the `#[server]` layer shows [FetchError::status_message] or
+/// [FetchError::message_with_body], whichever wording that call had before errors were typed.
+/// `context` on the HTTP variants names the failed step, e.g. "Etsy token refresh failed".
+#[derive(Debug, Clone, PartialEq)]
+pub enum FetchError {
+    /// No usable response: DNS, connect, timeout, or the body couldn't be read.
+    Network(String),
+    /// Credentials rejected (401/403) or a token that can't be refreshed.
+    Auth { context: &'static str, status: u16, body: String },
+    /// Still throttled (429) after retries; `retry_after` is the server's hint in seconds.
+    RateLimited { context: &'static str, retry_after: Option<u64> },
+    /// The response didn't have the expected shape.
+    Parse(String),
+    /// Required credentials or settings aren't set.
+    NotConfigured(String),
+    /// The call worked but the request can't be carried out as asked (e.g. nothing left
+    /// to fulfill).
+    Invalid(String),
+    /// Any other unsuccessful response.
+    Api { context: &'static str, status: u16, body: String },
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::Network(msg)
+            | FetchError::Parse(msg)
+            | FetchError::NotConfigured(msg)
+            | FetchError::Invalid(msg) => f.write_str(msg),
+            FetchError::Auth { context, status, body } => {
+                write!(f, "{}: not authorized (HTTP {}) {}", context, status, body)
+            }
+            FetchError::RateLimited { context, retry_after: Some(secs) } => {
+                write!(f, "{}: rate limited, retry after {} s", context, secs)
+            }
+            FetchError::RateLimited { context, retry_after: None } => write!(f, "{}: rate limited", context),
+            FetchError::Api { context, status, body } if body.is_empty() => {
+                write!(f, "{}: HTTP {}", context, status)
+            }
+            FetchError::Api { context, status, body } => write!(f, "{}: HTTP {} {}", context, status, body),
+        }
+    }
+}
+
+impl FetchError {
+    /// Classify an unsuccessful response, reading its body for context. `context` names
+    /// the failed step, e.g. "Shopify API error".
+    pub async fn from_response(resp: Response, context: &'static str) -> Self {
+        let status = resp.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return FetchError::RateLimited {
+                context,
+                retry_after: retry_after(&resp).map(|d| d.as_secs()),
+            };
+        }
+        let body = resp.text().await.unwrap_or_default();
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            return FetchError::Auth {
+                context,
+                status: status.as_u16(),
+                body,
+            };
+        }
+        FetchError::Api {
+            context,
+            status: status.as_u16(),
+            body,
+        }
+    }
+
+    /// "<context>: <status line>", e.g. "Shopify API error: 500 Internal Server Error".
+    /// A 2xx status says nothing on its own (GraphQL errors), so those show
+    /// "<context>: <body>". Other variants are their message as-is.
+    pub fn status_message(&self) -> String {
+        self.message(false)
+    }
+
+    /// [FetchError::status_message] plus " - <body>" when the response had one.
+    pub fn message_with_body(&self) -> String {
+        self.message(true)
+    }
+
+    fn message(&self, with_body: bool) -> String {
+        let (context, status, body) = match self {
+            FetchError::Auth { context, status, body } | FetchError::Api { context, status, body } => {
+                (*context, *status, body.as_str())
+            }
+            FetchError::RateLimited { context, .. } => (*context, StatusCode::TOO_MANY_REQUESTS.as_u16(), ""),
+            other => return other.to_string(),
+        };
+        let status_line = StatusCode::from_u16(status)
+            .map(|s| s.to_string())
+            .unwrap_or_else(|_| status.to_string());
+        if (200..300).contains(&status) {
+            format!("{}: {}", context, body)
+        } else if with_body && !body.is_empty() {
+            format!("{}: {} - {}", context, status_line, body)
+        } else {
+            format!("{}: {}", context, status_line)
+        }
+    }
+}
+
+/// Rate limiting (429) and server errors (5xx) are worth retrying; other statuses are final.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Send a request, retrying on statuses accepted by `should_retry` and on connect/timeout
+/// errors, up to `max_attempts` total. Waits for `Retry-After` (seconds) when the server
+/// sends it, else backs off exponentially. The last response or error is returned as-is,
+/// so callers keep their existing status handling.
+///
+/// Non-idempotent methods (POST, PATCH) are only retried when the server can't have acted
+/// on them: a 429 or a failed connect. A 5xx or timeout may follow a server-side success,
+/// and repeating e.g. a fulfillment or a token refresh would do it twice.
+pub async fn send_with_retry(
+    req_builder: RequestBuilder,
+    max_attempts: u32,
+    should_retry: impl Fn(StatusCode) -> bool,
+) -> reqwest::Result<Response> {
+    let max_attempts = max_attempts.max(1);
+    let idempotent = req_builder
+        .try_clone()
+        .and_then(|b| b.build().ok())
+        .is_some_and(|r| r.method().is_idempotent());
+    let retry_status =
+        |status: StatusCode| should_retry(status) && (idempotent || status == StatusCode::TOO_MANY_REQUESTS);
+    let mut attempt = 1;
+    loop {
+        // Bodies that can't be cloned (streams) get exactly one attempt.
+        let Some(req) = req_builder.try_clone() else {
+            return req_builder.send().await;
+        };
+        let last_attempt = attempt >= max_attempts;
+        let wait = match req.send().await {
+            Ok(resp) if !last_attempt && retry_status(resp.status()) => {
+                let wait = retry_after(&resp).unwrap_or_else(|| backoff(attempt));
+                log::app_log(
+                    "INFO",
+                    format!(
+                        "HTTP {} from {}, retrying in {} ms (attempt {}/{})",
+                        resp.status(),
+                        resp.url().path(),
+                        wait.as_millis(),
+                        attempt,
+                        max_attempts
+                    ),
+                );
+                wait
+            }
+            Err(e) if !last_attempt && (e.is_connect() || (idempotent && e.is_timeout())) => {
+                let wait = backoff(attempt);
+                log::app_log(
+                    "INFO",
+                    format!(
+                        "HTTP request failed ({}), retrying in {} ms (attempt {}/{})",
+                        e,
+                        wait.as_millis(),
+                        attempt,
+                        max_attempts
+                    ),
+                );
+                wait
+            }
+            other => return other,
+        };
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+    }
+}
+
+/// `Retry-After` as a delay, when given in seconds (HTTP-date values fall back to backoff).
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let secs: f64 = resp
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs).min(MAX_BACKOFF))
+}
+
+fn backoff(attempt: u32) -> Duration {
+    BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mount(server: &MockServer, verb: &str, response: ResponseTemplate, times: Option<u64>, expect: u64) {
+        let mock = Mock::given(method(verb)).and(path("/orders")).respond_with(response);
+        let mock = match times {
+            Some(n) => mock.up_to_n_times(n),
+            None => mock,
+        };
+        mock.expect(expect).mount(server).await;
+    }
+
+    fn get(server: &MockServer) -> RequestBuilder {
+        reqwest::Client::new().get(format!("{}/orders", server.uri()))
+    }
+
+    fn post(server: &MockServer) -> RequestBuilder {
+        reqwest::Client::new()
+            .post(format!("{}/orders", server.uri()))
+            .json(&serde_json::json!({ "fulfillment": {} }))
+    }
+
+    #[tokio::test]
+    async fn waits_for_retry_after_on_429() {
+        let server = MockServer::start().await;
+        let throttled = ResponseTemplate::new(429).insert_header("Retry-After", "1");
+        mount(&server, "GET", throttled, Some(1), 1).await;
+        mount(&server, "GET", ResponseTemplate::new(200), None, 1).await;
+
+        let started = Instant::now();
+        let resp = send_with_retry(get(&server), 3, is_retryable_status).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn retries_server_error_then_succeeds() {
+        let server = MockServer::start().await;
+        mount(&server, "GET", ResponseTemplate::new(503), Some(1), 1).await;
+        mount(&server, "GET", ResponseTemplate::new(200), None, 1).await;
+
+        let resp = send_with_retry(get(&server), 3, is_retryable_status).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let server = MockServer::start().await;
+        mount(&server, "GET", ResponseTemplate::new(500), None, 3).await;
+
+        let resp = send_with_retry(get(&server), 3, is_retryable_status).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_final() {
+        let server = MockServer::start().await;
+        mount(&server, "GET", ResponseTemplate::new(404), None, 1).await;
+
+        let resp = send_with_retry(get(&server), 3, is_retryable_status).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn post_is_not_retried_on_server_error() {
+        let server = MockServer::start().await;
+        mount(&server, "POST", ResponseTemplate::new(502), None, 1).await;
+
+        let resp = send_with_retry(post(&server), 3, is_retryable_status).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn post_is_retried_on_429() {
+        let server = MockServer::start().await;
+        let throttled = ResponseTemplate::new(429).insert_header("Retry-After", "0");
+        mount(&server, "POST", throttled, Some(1), 1).await;
+        mount(&server, "POST", ResponseTemplate::new(201), None, 1).await;
+
+        let resp = send_with_retry(post(&server), 3, is_retryable_status).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    #[test]
+    fn boundary_messages_match_the_untyped_wording() {
+        let err = FetchError::Api {
+            context: "Shopify API error",
+            status: 500,
+            body: "<html>oops</html>".to_string(),
+        };
+        assert_eq!(err.status_message(), "Shopify API error: 500 Internal Server Error");
+        assert_eq!(
+            err.message_with_body(),
+            "Shopify API error: 500 Internal Server Error - <html>oops</html>"
+        );
+
+        let graphql = FetchError::Api {
+            context: "Shopify GraphQL error",
+            status: 200,
+            body: "Throttled".to_string(),
+        };
+        assert_eq!(graphql.status_message(), "Shopify GraphQL error: Throttled");
+        assert_eq!(FetchError::Invalid("nothing open".to_string()).message_with_body(), "nothing open");
+    }
+}
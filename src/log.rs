@@ -2,33 +2,88 @@
 
 use std::sync::Mutex;
 
-const MAX_LOGS: usize = 2000;
+const DEFAULT_MAX_LOGS: usize = 2000;
+/// Upper bound for `LOG_BUFFER_MAX`, so a typo can't grow the buffer without limit.
+const MAX_LOGS_CEILING: usize = 100_000;
 
 static LOG_BUF: std::sync::OnceLock<Mutex<Vec<LogEntry>>> = std::sync::OnceLock::new();
 
 #[derive(Clone, Debug)]
 pub struct LogEntry {
+    /// Time of the most recent occurrence.
     pub time: String,
     pub level: String,
     pub message: String,
+    /// How many consecutive times this exact level + message was logged (1 = once).
+    pub count: u32,
+}
+
+impl LogEntry {
+    /// Message with a repeat suffix, e.g. "Shopify: page got 0 orders (x12)".
+    pub fn display_message(&self) -> String {
+        if self.count > 1 {
+            format!("{} (x{})", self.message, self.count)
+        } else {
+            self.message.clone()
+        }
+    }
 }
 
 fn buf() -> &'static Mutex<Vec<LogEntry>> {
     LOG_BUF.get_or_init(|| Mutex::new(Vec::new()))
 }
 
+static DEDUP: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+
+/// Collapse consecutive identical lines into one counted entry. On by default;
+/// set `LOG_DEDUP=0` (or `false`) to keep every line.
+fn dedup_enabled() -> bool {
+    *DEDUP.get_or_init(|| {
+        !matches!(
+            std::env::var("LOG_DEDUP").unwrap_or_default().trim().to_lowercase().as_str(),
+            "0" | "false" | "off"
+        )
+    })
+}
+
+static MAX_LOGS: std::sync::OnceLock<usize> = std::sync::OnceLock::new();
+
+/// Entries kept before the oldest are dropped: `LOG_BUFFER_MAX` (read once), clamped to
+/// 100..=[MAX_LOGS_CEILING], defaulting to [DEFAULT_MAX_LOGS] when unset or unparsable.
+pub fn max_logs() -> usize {
+    *MAX_LOGS.get_or_init(|| {
+        std::env::var("LOG_BUFFER_MAX")
+            .ok()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .map(|n| n.clamp(100, MAX_LOGS_CEILING))
+            .unwrap_or(DEFAULT_MAX_LOGS)
+    })
+}
+
 /// Append a log line. Safe to call from any thread (e.g. from async fetch).
+/// A repeat of the previous line (same level and message) bumps its count instead.
 pub fn app_log(level: &str, message: impl Into<String>) {
-    let entry = LogEntry {
-        time: chrono::Utc::now().format("%H:%M:%S%.3f").to_string(),
-        level: level.to_string(),
-        message: message.into(),
-    };
+    let time = chrono::Utc::now().format("%H:%M:%S%.3f").to_string();
+    let message = message.into();
     if let Ok(mut v) = buf().lock() {
-        v.push(entry);
+        let repeat = v
+            .last_mut()
+            .filter(|e| dedup_enabled() && e.level == level && e.message == message);
+        if let Some(last) = repeat {
+            last.count += 1;
+            last.time = time;
+            return;
+        }
+        v.push(LogEntry {
+            time,
+            level: level.to_string(),
+            message,
+            count: 1,
+        });
         let n = v.len();
-        if n > MAX_LOGS {
-            v.drain(0..n - MAX_LOGS);
+        let max = max_logs();
+        if n > max {
+            v.drain(0..n - max);
         }
     }
 }
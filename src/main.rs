@@ -3,37 +3,199 @@
 mod api;
 mod components;
 #[cfg(feature = "server")]
+mod config;
+#[cfg(feature = "server")]
+mod daily_queue;
+#[cfg(feature = "server")]
 mod db;
 #[cfg(feature = "server")]
 mod etsy;
+#[cfg(feature = "server")]
+mod http_util;
 mod log;
 mod model;
 #[cfg(feature = "server")]
 mod shopify;
 
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use dioxus::prelude::*;
+use std::collections::HashMap;
 use log::{app_logs_snapshot, LogEntry};
 
 use components::dialog::{DialogContent, DialogRoot, DialogTitle};
-use model::{lookup_piece_cost, ItemCostWeight, MetalType, Order, OrderItem, OrderSource, PieceCostRow};
+use model::{
+    lookup_piece_cost, lookup_piece_cost_match, EtsyTokenStatus, MetalType, Order, OrderItem, OrderSource, OrderStatus, PieceCostMatch,
+    MatchStrategy, PieceCostRow, SortBy, SourceToggles, UiPrefs, ViewFilter, WeightUnit, DEFAULT_PAGE_SIZE,
+    PAGE_SIZE_OPTIONS, COST_CURRENCY, format_money, unmatched_products, UnmatchedProduct, parse_display_tz,
+    COMMON_TIMEZONES, ExportFilter, OrdersExport, suggest_casting_batches, CastingPlan, DEFAULT_CRUCIBLE_CAPACITY_G,
+    WARNING_WITHIN_DAYS, AUTO_REFRESH_OPTIONS, DueThresholds,
+};
 
 // ============================================================================
-// App state
+// Helpers
 // ============================================================================
 
-#[derive(Debug, Clone, PartialEq)]
-enum ViewFilter {
-    All,
-    Shopify,
-    Etsy,
-    Urgent,
+/// Numeric search on an order's total: ">500", "<100", ">=250", "100-300".
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PriceQuery {
+    AtLeast(f64),
+    Above(f64),
+    AtMost(f64),
+    Below(f64),
+    Between(f64, f64),
+}
+
+impl PriceQuery {
+    /// Parse a search query as a price filter. Anything that isn't a well-formed price
+    /// expression (including "abc", ">", "100-") returns `None` and is searched as text.
+    /// A bare range that looks like a month ("2024-03") or a ring-size pair ("6-7") stays
+    /// text; a `$` or `price:` prefix makes it a price range anyway.
+    fn parse(query: &str) -> Option<Self> {
+        let query = query.trim();
+        let (explicit, expr) = match query.strip_prefix("price:") {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, query),
+        };
+        let q: String = expr.chars().filter(|c| !c.is_whitespace() && *c != '$').collect();
+        let num = |s: &str| s.parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0);
+        if let Some(rest) = q.strip_prefix(">=") {
+            num(rest).map(PriceQuery::AtLeast)
+        } else if let Some(rest) = q.strip_prefix("<=") {
+            num(rest).map(PriceQuery::AtMost)
+        } else if let Some(rest) = q.strip_prefix('>') {
+            num(rest).map(PriceQuery::Above)
+        } else if let Some(rest) = q.strip_prefix('<') {
+            num(rest).map(PriceQuery::Below)
+        } else if let Some((lo, hi)) = q.split_once('-') {
+            if !explicit && !expr.starts_with('$') && looks_like_month_or_sizes(lo, hi) {
+                return None;
+            }
+            let (lo, hi) = (num(lo)?, num(hi)?);
+            Some(PriceQuery::Between(lo.min(hi), lo.max(hi)))
+        } else {
+            None
+        }
+    }
+
+    fn matches(&self, total: f64) -> bool {
+        match *self {
+            PriceQuery::AtLeast(n) => total >= n,
+            PriceQuery::Above(n) => total > n,
+            PriceQuery::AtMost(n) => total <= n,
+            PriceQuery::Below(n) => total < n,
+            PriceQuery::Between(lo, hi) => (lo..=hi).contains(&total),
+        }
+    }
+}
+
+/// "2024-03" (year-month) or "6-7" / "6.5-8" (ring sizes 1-16): text, not a price range.
+fn looks_like_month_or_sizes(lo: &str, hi: &str) -> bool {
+    let digits = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    let is_month = digits(lo, 4) && digits(hi, 2) && (1..=12).contains(&hi.parse::<u32>().unwrap_or(0));
+    let is_size = |s: &str| s.parse::<f64>().is_ok_and(|n| (1.0..=16.0).contains(&n));
+    is_month || (is_size(lo) && is_size(hi))
+}
+
+/// Aggregated catalog cost and weight over a set of orders.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct QueueTotals {
+    cost_usd: f64,
+    weight_g: f64,
+    /// Line items with no piece_costs match (excluded from the totals).
+    unmatched: usize,
+}
+
+/// Data older than this is flagged as stale in the nav bar.
+const STALE_AFTER_SECS: i64 = 10 * 60;
+
+/// Human-friendly age: "just now", "5m ago", "2h ago", "3d ago".
+fn format_age(secs: i64) -> String {
+    if secs < 60 {
+        "just now".to_string()
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86_400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86_400)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum SortBy {
-    DueDate,
-    OrderDate,
-    Customer,
+/// Keydown handler for links and buttons inside the orders table: Enter activates the
+/// control itself and must not also reach the table's open-the-focused-row handling.
+fn keep_enter_local(evt: KeyboardEvent) {
+    if evt.key() == Key::Enter {
+        evt.stop_propagation();
+    }
+}
+
+/// Copy text to the system clipboard. Runs in the webview, so the same call works on
+/// web and desktop.
+async fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let literal = serde_json::to_string(text).map_err(|e| e.to_string())?;
+    document::eval(&format!("await navigator.clipboard.writeText({}); return true;", literal))
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Save `contents` as a file download named `file_name` (webview Blob + temporary link).
+async fn download_file(file_name: &str, mime: &str, contents: &str) -> Result<(), String> {
+    let name = serde_json::to_string(file_name).map_err(|e| e.to_string())?;
+    let mime = serde_json::to_string(mime).map_err(|e| e.to_string())?;
+    let body = serde_json::to_string(contents).map_err(|e| e.to_string())?;
+    let js = format!(
+        "const url = URL.createObjectURL(new Blob([{}], {{ type: {} }}));
+         const a = document.createElement('a');
+         a.href = url;
+         a.download = {};
+         document.body.appendChild(a);
+         a.click();
+         a.remove();
+         setTimeout(() => URL.revokeObjectURL(url), 1000);
+         return true;",
+        body, mime, name
+    );
+    document::eval(&js).await.map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Show a standalone HTML page in a new window and open the print dialog for it.
+async fn print_html(html: &str) -> Result<(), String> {
+    let body = serde_json::to_string(html).map_err(|e| e.to_string())?;
+    let js = format!(
+        "const w = window.open('', '_blank');
+         if (!w) {{ throw new Error('popup blocked'); }}
+         w.document.write({});
+         w.document.close();
+         w.focus();
+         w.print();
+         return true;",
+        body
+    );
+    document::eval(&js).await.map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Metals offered in the casting planner.
+const CASTING_METALS: [MetalType; 3] = [MetalType::Gold, MetalType::Silver, MetalType::Bronze];
+
+/// Days ahead the "Today's queue" print view reaches (0 = due today and overdue).
+const DAILY_QUEUE_WITHIN_DAYS: i64 = 0;
+
+/// Download `orders` as pretty JSON wrapped in [OrdersExport]; `filter` is `None` for "all".
+fn export_orders(orders: Vec<Order>, filter: Option<ExportFilter>) {
+    let export = OrdersExport::new(orders, filter);
+    let file_name = format!("orders-{}.json", export.exported_at.format("%Y%m%d-%H%M%S"));
+    spawn(async move {
+        let result = match serde_json::to_string_pretty(&export) {
+            Ok(json) => download_file(&file_name, "application/json", &json).await,
+            Err(e) => Err(e.to_string()),
+        };
+        match result {
+            Ok(()) => log::app_log("INFO", format!("Exported {} orders to {}", export.order_count, file_name)),
+            Err(e) => log::app_log("ERROR", format!("Export failed: {}", e)),
+        }
+    });
 }
 
 // ============================================================================
@@ -52,70 +214,322 @@ fn main() {
 fn App() -> Element {
     let mut orders = use_signal(Vec::<Order>::new);
     let mut loading = use_signal(|| true);
+    let mut last_updated = use_signal(|| None::<DateTime<Utc>>);
+    // Bumped every 30s so "Updated Xm ago" keeps counting without new data.
+    let mut clock_tick = use_signal(|| 0u64);
     let mut error = use_signal(|| None::<String>);
+    // The last fetch failed for want of credentials (shows setup help under the error).
+    let mut needs_setup = use_signal(|| false);
     let mut view_filter = use_signal(|| ViewFilter::All);
     let mut sort_by = use_signal(|| SortBy::DueDate);
+    let mut weight_unit = use_signal(WeightUnit::default);
+    let mut show_empty_orders = use_signal(|| false);
+    let mut totals_include_shipped = use_signal(|| false);
+    // Debug-only, so not persisted with the other view preferences.
+    let mut show_test_orders = use_signal(|| false);
+    let mut status_filter = use_signal(|| None::<OrderStatus>);
+    let mut show_cancelled = use_signal(|| false);
+    let mut page_size = use_signal(|| DEFAULT_PAGE_SIZE);
+    // Zone for every displayed date and day count; changing it re-renders without a refetch.
+    let mut display_tz = use_signal(|| Tz::UTC);
+    // Zero-based page of filtered_orders shown in the table.
+    let mut current_page = use_signal(|| 0usize);
     let mut search_query = use_signal(String::new);
+    let mut currency_filter = use_signal(|| None::<String>);
+    let mut location_filter = use_signal(|| None::<String>);
     let mut settings_open = use_signal(|| false);
     let mut etsy_token_input = use_signal(String::new);
     let mut etsy_save_message = use_signal(|| None::<String>);
+    let mut source_toggles = use_signal(SourceToggles::default);
+    let mut source_save_message = use_signal(|| None::<String>);
+    let mut etsy_token_status = use_signal(|| None::<EtsyTokenStatus>);
     let mut detail_order = use_signal(|| None::<Order>);
+    // Keyboard-selected row index into filtered_orders (arrow keys move it, Enter opens it).
+    let mut focused_row = use_signal(|| None::<usize>);
     let mut logs_open = use_signal(|| false);
+    let mut unmatched_open = use_signal(|| false);
+    let mut export_open = use_signal(|| false);
+    let mut casting_open = use_signal(|| false);
+    let mut casting_metal = use_signal(|| MetalType::Silver);
+    let mut casting_within_days = use_signal(|| WARNING_WITHIN_DAYS);
+    let mut crucible_capacity_g = use_signal(|| DEFAULT_CRUCIBLE_CAPACITY_G);
     let mut log_snapshot = use_signal(|| Vec::<LogEntry>::new());
     let mut piece_costs_cache = use_signal(|| Vec::<PieceCostRow>::new());
+    let mut archived_ids = use_signal(Vec::<String>::new);
+    let mut order_notes = use_signal(HashMap::<String, String>::new);
+    // Prefs as last loaded or saved; `None` until loaded, so nothing is saved before that.
+    let mut saved_prefs = use_signal(|| None::<UiPrefs>);
+    let mut auto_refresh_minutes = use_signal(|| 0u32);
+    let mut due_thresholds = use_signal(DueThresholds::default);
+    let mut cost_currency = use_signal(|| COST_CURRENCY.to_string());
 
-    use_effect(move || {
+    // Re-checked after every fetch, since fetching is what refreshes the Etsy token.
+    let reload_etsy_token_status = move || {
         spawn(async move {
-            match api::fetch_piece_costs().await {
-                Ok(rows) => piece_costs_cache.set(rows),
-                Err(e) => log::app_log("INFO", format!("Piece costs load: {}", e)),
+            match api::etsy_token_status().await {
+                Ok(status) => etsy_token_status.set(Some(status)),
+                Err(e) => log::app_log("INFO", format!("Etsy token status: {}", e)),
             }
         });
-    });
+    };
 
-    use_effect(move || {
+    // Shared by the initial load, the Refresh button and auto-refresh.
+    let mut refresh_orders = move || {
+        loading.set(true);
+        error.set(None);
+        needs_setup.set(false);
         spawn(async move {
-            loading.set(true);
-            error.set(None);
-            log::app_log("INFO", "Fetching orders...");
-            match api::fetch_all_orders().await {
+            log::app_log("INFO", "Refresh: fetching orders...");
+            match api::fetch_all_orders(api::FetchOptions::default()).await {
                 Ok(result) => {
                     let total = result.orders.len();
-                    log::app_log("INFO", format!("Got {} total orders.", total));
+                    log::app_log("INFO", format!("Refresh done. {} total orders.", total));
                     for err in &result.errors {
                         log::app_log("ERROR", err.clone());
                     }
                     if let Some(first_err) = result.errors.first() {
                         error.set(Some(first_err.clone()));
                     }
+                    needs_setup.set(result.not_configured);
                     orders.set(result.orders);
+                    last_updated.set(Some(Utc::now()));
                 }
                 Err(e) => {
-                    log::app_log("ERROR", format!("Fetch failed: {}", e));
+                    log::app_log("ERROR", format!("Refresh error: {}", e));
                     error.set(Some(e.to_string()));
                 }
             }
+            reload_etsy_token_status();
             loading.set(false);
         });
+    };
+
+    let mut open_settings = move || {
+        settings_open.set(true);
+        etsy_save_message.set(None);
+        source_save_message.set(None);
+        spawn(async move {
+            match api::load_source_toggles().await {
+                Ok(toggles) => source_toggles.set(toggles),
+                Err(e) => log::app_log("ERROR", format!("Source toggles load: {}", e)),
+            }
+        });
+    };
+
+    use_effect(move || {
+        spawn(async move {
+            match api::load_settings().await {
+                Ok(settings) => {
+                    auto_refresh_minutes.set(settings.auto_refresh_minutes.unwrap_or(0));
+                    due_thresholds.set(settings.due_thresholds());
+                    cost_currency.set(settings.cost_currency().to_string());
+                }
+                Err(e) => log::app_log("INFO", format!("Settings load: {}", e)),
+            }
+        });
+    });
+
+    // View preferences as currently shown (reading it subscribes to every one of them).
+    let current_prefs = move || UiPrefs {
+        view_filter: view_filter.read().clone(),
+        sort_by: sort_by.read().clone(),
+        weight_unit: *weight_unit.read(),
+        show_empty_orders: *show_empty_orders.read(),
+        status_filter: *status_filter.read(),
+        show_cancelled: *show_cancelled.read(),
+        page_size: *page_size.read(),
+        display_tz: Some(display_tz.read().name().to_string()),
+        totals_include_shipped: *totals_include_shipped.read(),
+        crucible_capacity_g: *crucible_capacity_g.read(),
+    };
+
+    use_effect(move || {
+        spawn(async move {
+            match api::load_ui_prefs().await {
+                Ok(prefs) => {
+                    view_filter.set(prefs.view_filter);
+                    sort_by.set(prefs.sort_by);
+                    weight_unit.set(prefs.weight_unit);
+                    show_empty_orders.set(prefs.show_empty_orders);
+                    totals_include_shipped.set(prefs.totals_include_shipped);
+                    crucible_capacity_g.set(prefs.crucible_capacity_g);
+                    status_filter.set(prefs.status_filter);
+                    show_cancelled.set(prefs.show_cancelled);
+                    page_size.set(prefs.page_size.max(1));
+                    if let Some(tz) = prefs.display_tz.as_deref().and_then(|name| parse_display_tz(name).ok()) {
+                        display_tz.set(tz);
+                    }
+                }
+                Err(e) => log::app_log("INFO", format!("UI prefs load: {}", e)),
+            }
+            saved_prefs.set(Some(current_prefs()));
+        });
+    });
+
+    // Persist filter + sort when the user changes them (search query is deliberately left out).
+    // Applying the loaded prefs matches the snapshot, so loading never writes anything back.
+    use_effect(move || {
+        let prefs = current_prefs();
+        let Some(saved) = saved_prefs.peek().clone() else {
+            return;
+        };
+        if prefs == saved {
+            return;
+        }
+        // An unchanged zone is sent as `None` so it keeps following `DISPLAY_TZ` until picked here.
+        let mut update = prefs.clone();
+        if update.display_tz == saved.display_tz {
+            update.display_tz = None;
+        }
+        saved_prefs.set(Some(prefs));
+        spawn(async move {
+            if let Err(e) = api::save_ui_prefs(update).await {
+                log::app_log("ERROR", format!("UI prefs save: {}", e));
+            }
+        });
+    });
+
+    use_effect(move || {
+        spawn(async move {
+            match api::fetch_piece_costs().await {
+                Ok(rows) => piece_costs_cache.set(rows),
+                Err(e) => log::app_log("INFO", format!("Piece costs load: {}", e)),
+            }
+        });
+    });
+
+    use_effect(move || {
+        spawn(async move {
+            match api::fetch_archived_order_ids().await {
+                Ok(ids) => archived_ids.set(ids),
+                Err(e) => log::app_log("INFO", format!("Archived orders load: {}", e)),
+            }
+        });
+    });
+
+    use_effect(move || {
+        spawn(async move {
+            match api::load_order_notes().await {
+                Ok(notes) => order_notes.set(notes),
+                Err(e) => log::app_log("INFO", format!("Order notes load: {}", e)),
+            }
+        });
+    });
+
+    // Initial load; the same fetch as the Refresh button.
+    use_effect(move || refresh_orders());
+
+    let currencies = use_memo(move || {
+        let mut codes: Vec<String> = orders.read().iter().map(|o| o.currency.clone()).collect();
+        codes.sort();
+        codes.dedup();
+        codes
+    });
+
+    let locations = use_memo(move || {
+        let mut names: Vec<String> = orders
+            .read()
+            .iter()
+            .flat_map(|o| o.item_locations().into_iter().map(str::to_string))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    });
+
+    // Drop a currency selection that no longer matches any loaded order (e.g. after a refresh).
+    use_effect(move || {
+        let available = currencies.read();
+        let stale = currency_filter
+            .peek()
+            .as_ref()
+            .is_some_and(|c| !available.contains(c));
+        if stale {
+            currency_filter.set(None);
+        }
+    });
+
+    use_effect(move || {
+        let available = locations.read();
+        let stale = location_filter
+            .peek()
+            .as_ref()
+            .is_some_and(|loc| !available.contains(loc));
+        if stale {
+            location_filter.set(None);
+        }
+    });
+
+    use_future(move || async move {
+        loop {
+            let _ = document::eval("await new Promise(r => setTimeout(r, 30000)); return true;").await;
+            clock_tick += 1;
+        }
+    });
+
+    // Auto-refresh rides the 30 s clock tick; it waits for a first successful fetch.
+    use_effect(move || {
+        let _ = clock_tick.read();
+        let minutes = *auto_refresh_minutes.read();
+        if minutes == 0 || *loading.peek() {
+            return;
+        }
+        let due = last_updated
+            .peek()
+            .is_some_and(|at| (Utc::now() - at).num_minutes() >= i64::from(minutes));
+        if due {
+            refresh_orders();
+        }
     });
 
     let filtered_orders = use_memo(move || {
+        let tz = display_tz();
         let mut result: Vec<Order> = orders
             .read()
             .iter()
             .filter(|order| {
+                let is_archived = archived_ids.read().contains(&order.id);
                 let passes_filter = match *view_filter.read() {
+                    ViewFilter::Archived => is_archived,
+                    _ if is_archived => false,
                     ViewFilter::All => true,
                     ViewFilter::Shopify => matches!(order.source, OrderSource::Shopify),
                     ViewFilter::Etsy => matches!(order.source, OrderSource::Etsy),
-                    ViewFilter::Urgent => order.days_until_due() <= 3,
+                    ViewFilter::Urgent => order.is_urgent(tz, due_thresholds()),
                 };
+                let passes_location = location_filter
+                    .read()
+                    .as_ref()
+                    .is_none_or(|loc| order.item_locations().contains(&loc.as_str()));
+                let passes_currency = currency_filter
+                    .read()
+                    .as_ref()
+                    .is_none_or(|c| &order.currency == c);
                 let query = search_query.read().to_lowercase();
-                let passes_search = query.is_empty()
-                    || order.customer_name.to_lowercase().contains(&query)
-                    || order.order_number.to_lowercase().contains(&query)
-                    || order.items.iter().any(|item| item.name.to_lowercase().contains(&query));
-                passes_filter && passes_search
+                let passes_search = if let Some(price_query) = PriceQuery::parse(&query) {
+                    price_query.matches(order.total_price)
+                } else {
+                    query.is_empty()
+                        || order.customer_name.to_lowercase().contains(&query)
+                        || order.order_number.to_lowercase().contains(&query)
+                        || order.items.iter().any(|item| item.name.to_lowercase().contains(&query))
+                        || order
+                            .shipping_address
+                            .as_ref()
+                            .is_some_and(|addr| addr.matches_search(&query))
+                        || order_notes
+                            .read()
+                            .get(&order.id)
+                            .is_some_and(|note| note.to_lowercase().contains(&query))
+                };
+                let passes_items = order.has_items() || *show_empty_orders.read();
+                let passes_test = !order.is_test || *show_test_orders.read();
+                // Picking "Cancelled" or "Refunded" explicitly shows them even with the toggle off.
+                let passes_status = match *status_filter.read() {
+                    Some(status) => order.status == status,
+                    None => !order.is_inactive() || *show_cancelled.read(),
+                };
+                passes_filter && passes_items && passes_test && passes_status && passes_location && passes_currency && passes_search
             })
             .cloned()
             .collect();
@@ -127,22 +541,128 @@ fn App() -> Element {
         result
     });
 
+    // Keep the keyboard selection inside the list when filters shrink it.
+    use_effect(move || {
+        let len = filtered_orders.read().len();
+        let current = *focused_row.peek();
+        match current {
+            Some(_) if len == 0 => focused_row.set(None),
+            Some(idx) if idx >= len => focused_row.set(Some(len - 1)),
+            _ => {}
+        }
+    });
+
+    // Stats cover the active queue only (see Order::counts_in_stats), the same orders the
+    // server's FetchSummary counts.
     let stats = use_memo(move || {
         let all = orders.read();
-        let total = all.len();
-        let shopify = all.iter().filter(|o| matches!(o.source, OrderSource::Shopify)).count();
-        let etsy = all.iter().filter(|o| matches!(o.source, OrderSource::Etsy)).count();
-        let urgent = all.iter().filter(|o| o.days_until_due() <= 3).count();
-        let overdue = all.iter().filter(|o| o.days_until_due() < 0).count();
+        let archived = archived_ids.read();
+        let active: Vec<&Order> = all.iter().filter(|o| o.counts_in_stats(&archived)).collect();
+        let total = active.len();
+        let shopify = active.iter().filter(|o| matches!(o.source, OrderSource::Shopify)).count();
+        let etsy = active.iter().filter(|o| matches!(o.source, OrderSource::Etsy)).count();
+        let tz = display_tz();
+        let thresholds = due_thresholds();
+        let urgent = active.iter().filter(|o| o.is_urgent(tz, thresholds)).count();
+        let overdue = active.iter().filter(|o| o.is_overdue(tz)).count();
         (total, shopify, etsy, urgent, overdue)
     });
 
+    // Units across the same active orders as `stats`, with a per-metal breakdown (most first).
+    let item_totals = use_memo(move || {
+        let all = orders.read();
+        let archived = archived_ids.read();
+        let mut total = 0u32;
+        let mut by_metal: Vec<(String, u32)> = Vec::new();
+        let items = all
+            .iter()
+            .filter(|o| o.counts_in_stats(&archived))
+            .flat_map(|o| o.items.iter());
+        for item in items {
+            total += item.quantity;
+            let metal = item.metal_label();
+            match by_metal.iter_mut().find(|(m, _)| *m == metal) {
+                Some((_, qty)) => *qty += item.quantity,
+                None => by_metal.push((metal, item.quantity)),
+            }
+        }
+        by_metal.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        (total, by_metal)
+    });
+
+    // Catalog cost + metal weight of the filtered queue; unmatched items are excluded and counted.
+    // Shipped items are left out unless the toggle says otherwise, so this is what's left to cast.
+    let queue_totals = use_memo(move || {
+        let costs = piece_costs_cache.read();
+        let include_shipped = *totals_include_shipped.read();
+        let mut totals = QueueTotals::default();
+        let orders = filtered_orders.read();
+        let items = orders
+            .iter()
+            .flat_map(|o| o.items.iter().filter(move |i| include_shipped || !o.item_shipped(i)));
+        for item in items {
+            match lookup_piece_cost(item, &costs) {
+                Some(cw) => {
+                    let q = item.quantity as f64;
+                    totals.cost_usd += cw.cost_usd * q;
+                    totals.weight_g += cw.weight_g * q;
+                }
+                None => totals.unmatched += 1,
+            }
+        }
+        totals
+    });
+
+    // Batches for the casting planner; archived orders are on hold, so they're left out.
+    let casting_plan = use_memo(move || {
+        let archived = archived_ids.read();
+        let live: Vec<Order> = orders
+            .read()
+            .iter()
+            .filter(|o| !o.is_test && !archived.contains(&o.id))
+            .cloned()
+            .collect();
+        suggest_casting_batches(
+            &live,
+            &piece_costs_cache.read(),
+            &casting_metal.read(),
+            *casting_within_days.read(),
+            *crucible_capacity_g.read(),
+            display_tz(),
+        )
+    });
+
+    let empty_order_count = use_memo(move || orders.read().iter().filter(|o| !o.has_items()).count());
+    let test_order_count = use_memo(move || orders.read().iter().filter(|o| o.is_test).count());
+    // Items in live (not cancelled/refunded) orders with no piece_costs row, for the report dialog.
+    let unmatched_report = use_memo(move || {
+        let live: Vec<Order> = orders.read().iter().filter(|o| !o.is_inactive() && !o.is_test).cloned().collect();
+        unmatched_products(&live, &piece_costs_cache.read())
+    });
+
+    let inactive_count = use_memo(move || orders.read().iter().filter(|o| o.is_inactive()).count());
+
+    let page_count = use_memo(move || filtered_orders.read().len().div_ceil(page_size()).max(1));
+
+    // Filters or a bigger page size can shrink the page count; never sit past the last page.
+    use_effect(move || {
+        let last = page_count() - 1;
+        if *current_page.peek() > last {
+            current_page.set(last);
+        }
+    });
+
+    // Rows on the current page, each with its index into filtered_orders.
     let orders_for_table = use_memo(move || {
+        let start = current_page() * page_size();
         filtered_orders
             .read()
             .iter()
-            .map(|o| (o.clone(), o.clone()))
-            .collect::<Vec<(Order, Order)>>()
+            .enumerate()
+            .skip(start)
+            .take(page_size())
+            .map(|(idx, o)| (idx, o.clone(), o.clone()))
+            .collect::<Vec<(usize, Order, Order)>>()
     });
 
     rsx! {
@@ -150,7 +670,14 @@ fn App() -> Element {
         document::Stylesheet { href: asset!("/assets/dx-components-theme.css") }
         document::Stylesheet { href: asset!("/assets/dialog.css") }
 
-        div { class: "bg-galaxy min-h-screen",
+        div {
+            class: "bg-galaxy min-h-screen",
+            // Escape closes the detail dialog from anywhere in the app, not just the table.
+            onkeydown: move |evt: KeyboardEvent| {
+                if evt.key() == Key::Escape && detail_order.peek().is_some() {
+                    detail_order.set(None);
+                }
+            },
             nav { class: "nav-galaxy px-6 py-4",
                 div { class: "container flex items-center justify-between flex-wrap gap-3",
                     div { class: "flex items-center gap-4",
@@ -161,6 +688,26 @@ fn App() -> Element {
                             span { class: "live-dot" }
                             span { class: "text-sm text-stardust", "Live" }
                         }
+                        {
+                            let _ = clock_tick.read();
+                            match *last_updated.read() {
+                                Some(at) => {
+                                    let age_secs = (Utc::now() - at).num_seconds().max(0);
+                                    let class = if age_secs > STALE_AFTER_SECS {
+                                        "text-sm text-comet-gold"
+                                    } else {
+                                        "text-sm text-stardust"
+                                    };
+                                    let label = format!("Updated {}", format_age(age_secs));
+                                    let title = format!(
+                                        "Last successful fetch: {}",
+                                        at.with_timezone(&*display_tz.read()).format("%b %d, %H:%M:%S %Z")
+                                    );
+                                    rsx! { span { class: "{class}", title: "{title}", "{label}" } }
+                                }
+                                None => rsx! { span { class: "text-sm text-stardust", "Not updated yet" } },
+                            }
+                        }
                         div { class: "nav-stats text-stardust text-sm flex items-center gap-4 flex-wrap",
                             span { "{stats.read().0} orders" }
                             span { "{stats.read().1} Shopify" }
@@ -172,40 +719,47 @@ fn App() -> Element {
                     div { class: "flex items-center gap-3",
                         button {
                             class: "btn-cosmic",
+                            onclick: move |_| refresh_orders(),
+                            "Refresh"
+                        }
+                        button {
+                            class: "btn-cosmic",
+                            onclick: move |_| open_settings(),
+                            "Settings"
+                        }
+                        button {
+                            class: "btn-cosmic",
+                            title: "Products with no piece_costs match",
+                            onclick: move |_| unmatched_open.set(true),
+                            "Unmatched ({unmatched_report.read().len()})"
+                        }
+                        button {
+                            class: "btn-cosmic",
+                            title: "Suggest casting batches per metal",
+                            onclick: move |_| casting_open.set(true),
+                            "Casting"
+                        }
+                        button {
+                            class: "btn-cosmic",
+                            title: "Printable work order of everything due today or overdue, by metal",
                             onclick: move |_| {
-                                loading.set(true);
-                                error.set(None);
                                 spawn(async move {
-                                    log::app_log("INFO", "Refresh: fetching orders...");
-                                    match api::fetch_all_orders().await {
-                                        Ok(result) => {
-                                            let total = result.orders.len();
-                                            log::app_log("INFO", format!("Refresh done. {} total orders.", total));
-                                            for err in &result.errors {
-                                                log::app_log("ERROR", err.clone());
-                                            }
-                                            if let Some(first_err) = result.errors.first() {
-                                                error.set(Some(first_err.clone()));
-                                            }
-                                            orders.set(result.orders);
-                                        }
-                                        Err(e) => {
-                                            log::app_log("ERROR", format!("Refresh error: {}", e));
-                                            error.set(Some(e.to_string()));
-                                        }
+                                    let result = match api::daily_queue_html(DAILY_QUEUE_WITHIN_DAYS).await {
+                                        Ok(html) => print_html(&html).await,
+                                        Err(e) => Err(e.to_string()),
+                                    };
+                                    if let Err(e) = result {
+                                        log::app_log("ERROR", format!("Today's queue failed: {}", e));
                                     }
-                                    loading.set(false);
                                 });
                             },
-                            "Refresh"
+                            "Today's queue"
                         }
                         button {
                             class: "btn-cosmic",
-                            onclick: move |_| {
-                                settings_open.set(true);
-                                etsy_save_message.set(None);
-                            },
-                            "Settings"
+                            title: "Download orders as JSON",
+                            onclick: move |_| export_open.set(true),
+                            "Export"
                         }
                         button {
                             class: "btn-cosmic",
@@ -228,6 +782,93 @@ fn App() -> Element {
                             onclick: move |evt| { evt.stop_propagation(); },
                             h2 { class: "text-xl font-bold text-star-white mb-4", "Settings" }
                             div { class: "space-y-4",
+                                div {
+                                    class: "border border-nebula-purple rounded-lg p-4",
+                                    h3 { class: "text-star-white font-medium mb-2", "Display timezone" }
+                                    p { class: "text-stardust text-sm mb-3",
+                                        "Dates and days-left are shown in this zone."
+                                    }
+                                    select {
+                                        class: "bg-nebula-dark border border-nebula-purple rounded-lg px-3 py-2",
+                                        onchange: move |evt| match parse_display_tz(&evt.value()) {
+                                            Ok(tz) => display_tz.set(tz),
+                                            Err(e) => log::app_log("ERROR", e),
+                                        },
+                                        if !COMMON_TIMEZONES.contains(&display_tz.read().name()) {
+                                            option { value: "{display_tz.read().name()}", selected: true, "{display_tz.read().name()}" }
+                                        }
+                                        for name in COMMON_TIMEZONES {
+                                            option { value: "{name}", selected: display_tz.read().name() == name, "{name}" }
+                                        }
+                                    }
+                                }
+                                div {
+                                    class: "border border-nebula-purple rounded-lg p-4",
+                                    h3 { class: "text-star-white font-medium mb-2", "Auto-refresh" }
+                                    select {
+                                        class: "bg-nebula-dark border border-nebula-purple rounded-lg px-3 py-2",
+                                        onchange: move |evt| {
+                                            let Ok(minutes) = evt.value().parse::<u32>() else {
+                                                return;
+                                            };
+                                            auto_refresh_minutes.set(minutes);
+                                            spawn(async move {
+                                                if let Err(e) = api::save_auto_refresh_minutes(minutes).await {
+                                                    log::app_log("ERROR", format!("Settings save: {}", e));
+                                                }
+                                            });
+                                        },
+                                        for minutes in AUTO_REFRESH_OPTIONS {
+                                            option {
+                                                value: "{minutes}",
+                                                selected: *auto_refresh_minutes.read() == minutes,
+                                                if minutes == 0 { "Off" } else { "Every {minutes} min" }
+                                            }
+                                        }
+                                    }
+                                }
+                                div {
+                                    class: "border border-nebula-purple rounded-lg p-4",
+                                    h3 { class: "text-star-white font-medium mb-2", "Order sources" }
+                                    p { class: "text-stardust text-sm mb-3",
+                                        "Disabled sources are skipped on refresh (no errors reported)."
+                                    }
+                                    div { class: "space-y-2",
+                                        SettingsToggle {
+                                            label: "Fetch Shopify",
+                                            checked: source_toggles.read().fetch_shopify,
+                                            onchange: move |on: bool| {
+                                                source_toggles.write().fetch_shopify = on;
+                                                let toggles = source_toggles.read().clone();
+                                                spawn(async move {
+                                                    match api::save_source_toggles(toggles).await {
+                                                        Ok(()) => source_save_message.set(Some("Saved. Refresh orders to apply.".to_string())),
+                                                        Err(e) => source_save_message.set(Some(e.to_string())),
+                                                    }
+                                                });
+                                            }
+                                        }
+                                        SettingsToggle {
+                                            label: "Fetch Etsy",
+                                            checked: source_toggles.read().fetch_etsy,
+                                            onchange: move |on: bool| {
+                                                source_toggles.write().fetch_etsy = on;
+                                                let toggles = source_toggles.read().clone();
+                                                spawn(async move {
+                                                    match api::save_source_toggles(toggles).await {
+                                                        Ok(()) => source_save_message.set(Some("Saved. Refresh orders to apply.".to_string())),
+                                                        Err(e) => source_save_message.set(Some(e.to_string())),
+                                                    }
+                                                });
+                                            }
+                                        }
+                                    }
+                                    {if let Some(msg) = source_save_message.read().as_ref() {
+                                        rsx! { p { class: "text-sm mt-2 text-stardust", "{msg}" } }
+                                    } else {
+                                        rsx! { }
+                                    }}
+                                }
                                 div {
                                     class: "border border-nebula-purple rounded-lg p-4",
                                     h3 { class: "text-star-white font-medium mb-2", "Connect Etsy" }
@@ -260,6 +901,7 @@ fn App() -> Element {
                                                         Ok(()) => {
                                                             etsy_save_message.set(Some("Etsy connected. Refresh orders to load Etsy.".to_string()));
                                                             etsy_token_input.set(String::new());
+                                                            reload_etsy_token_status();
                                                         }
                                                         Err(e) => etsy_save_message.set(Some(e.to_string())),
                                                     }
@@ -301,7 +943,7 @@ fn App() -> Element {
                             div { class: "log-line py-0.5",
                                 span { class: "text-stardust mr-2", "{entry.time}" }
                                 span { class: if entry.level == "ERROR" { "text-warning-red font-semibold" } else { "text-aurora-purple" }, "{entry.level}" }
-                                span { class: "text-moonlight ml-2", "{entry.message}" }
+                                span { class: "text-moonlight ml-2", "{entry.display_message()}" }
                             }
                         }
                     }
@@ -313,8 +955,139 @@ fn App() -> Element {
                         }
                         button {
                             class: "btn-cosmic",
-                            onclick: move |_| logs_open.set(false),
-                            "Close"
+                            onclick: move |_| logs_open.set(false),
+                            "Close"
+                        }
+                    }
+                }
+            }
+
+            DialogRoot {
+                open: *unmatched_open.read(),
+                on_open_change: move |open: bool| unmatched_open.set(open),
+                DialogContent {
+                    class: "flex flex-col max-w-3xl max-h-[85vh]",
+                    DialogTitle { "Unmatched products" }
+                    p { class: "text-stardust text-sm",
+                        "Items in current orders with no piece_costs row. Add a design_key or product_keys entry to price them."
+                    }
+                    UnmatchedProductsTable { products: unmatched_report() }
+                    div { class: "flex gap-2 mt-4",
+                        button {
+                            class: "btn-cosmic",
+                            onclick: move |_| unmatched_open.set(false),
+                            "Close"
+                        }
+                    }
+                }
+            }
+
+            DialogRoot {
+                open: *casting_open.read(),
+                on_open_change: move |open: bool| casting_open.set(open),
+                DialogContent {
+                    class: "flex flex-col max-w-3xl max-h-[85vh]",
+                    DialogTitle { "Casting batches" }
+                    p { class: "text-stardust text-sm",
+                        "Unshipped pieces due soon, earliest first, packed into crucible loads by catalog weight."
+                    }
+                    div { class: "flex flex-wrap items-center gap-4 mt-3",
+                        div { class: "flex items-center gap-2",
+                            span { class: "text-stardust text-sm", "Metal:" }
+                            select {
+                                class: "bg-nebula-dark border border-nebula-purple rounded-lg px-3 py-2",
+                                onchange: move |evt| {
+                                    let picked = CASTING_METALS.into_iter().find(|m| m.display_name() == evt.value());
+                                    if let Some(metal) = picked {
+                                        casting_metal.set(metal);
+                                    }
+                                },
+                                for metal in CASTING_METALS {
+                                    option {
+                                        value: "{metal.display_name()}",
+                                        selected: *casting_metal.read() == metal,
+                                        "{metal.display_name()}"
+                                    }
+                                }
+                            }
+                        }
+                        label { class: "flex items-center gap-2 text-stardust text-sm",
+                            "Due within"
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                class: "w-20 bg-nebula-dark border border-nebula-purple rounded-lg px-2 py-1 text-star-white",
+                                value: "{casting_within_days}",
+                                oninput: move |evt| {
+                                    if let Ok(days) = evt.value().trim().parse::<i64>() {
+                                        casting_within_days.set(days.max(0));
+                                    }
+                                }
+                            }
+                            "days"
+                        }
+                        label { class: "flex items-center gap-2 text-stardust text-sm",
+                            "Crucible"
+                            input {
+                                r#type: "number",
+                                min: "1",
+                                class: "w-20 bg-nebula-dark border border-nebula-purple rounded-lg px-2 py-1 text-star-white",
+                                value: "{crucible_capacity_g}",
+                                oninput: move |evt| {
+                                    let grams = evt.value().trim().parse::<f64>().ok().filter(|g| *g > 0.0);
+                                    if let Some(grams) = grams {
+                                        crucible_capacity_g.set(grams);
+                                    }
+                                }
+                            }
+                            "g"
+                        }
+                    }
+                    CastingPlanView { plan: casting_plan(), weight_unit: weight_unit() }
+                    div { class: "flex gap-2 mt-4",
+                        button {
+                            class: "btn-cosmic",
+                            onclick: move |_| casting_open.set(false),
+                            "Close"
+                        }
+                    }
+                }
+            }
+
+            DialogRoot {
+                open: *export_open.read(),
+                on_open_change: move |open: bool| export_open.set(open),
+                DialogContent {
+                    class: "flex flex-col max-w-md",
+                    DialogTitle { "Export orders" }
+                    p { class: "text-stardust text-sm",
+                        "Pretty JSON with the export time, per-source counts and the filters in use."
+                    }
+                    div { class: "flex gap-2 mt-4",
+                        button {
+                            class: "btn-nebula",
+                            onclick: move |_| {
+                                let filter = ExportFilter {
+                                    view: view_filter.read().clone(),
+                                    status: *status_filter.read(),
+                                    show_cancelled: *show_cancelled.read(),
+                                    show_empty_orders: *show_empty_orders.read(),
+                                    location: location_filter.read().clone(),
+                                    currency: currency_filter.read().clone(),
+                                    search: search_query.read().clone(),
+                                };
+                                export_orders(filtered_orders(), Some(filter));
+                                export_open.set(false);
+                            },
+                            "Current view ({filtered_orders.read().len()})"
+                        }
+                        button {
+                            class: "btn-cosmic",
+                            onclick: move |_| {
+                                export_orders(orders(), None);
+                                export_open.set(false);
+                            },
+                            "All orders ({orders.read().len()})"
                         }
                     }
                 }
@@ -334,6 +1107,104 @@ fn App() -> Element {
                             OrderDetailDialog {
                                 order: order.clone(),
                                 piece_costs: piece_costs_cache.read().clone(),
+                                weight_unit: weight_unit(),
+                                tz: display_tz(),
+                                cost_currency: cost_currency(),
+                                archived: archived_ids.read().contains(&order.id),
+                                note: order_notes.read().get(&order.id).cloned().unwrap_or_default(),
+                                on_save_note: move |note: String| {
+                                    let Some(order_id) = detail_order.read().as_ref().map(|o| o.id.clone()) else {
+                                        return;
+                                    };
+                                    spawn(async move {
+                                        match api::save_order_note(order_id.clone(), note.clone()).await {
+                                            Ok(()) => {
+                                                let note = note.trim().to_string();
+                                                if note.is_empty() {
+                                                    order_notes.write().remove(&order_id);
+                                                } else {
+                                                    order_notes.write().insert(order_id, note);
+                                                }
+                                            }
+                                            Err(e) => log::app_log("ERROR", format!("Note save failed: {}", e)),
+                                        }
+                                    });
+                                },
+                                on_toggle_archive: move |_| {
+                                    let Some(order_id) = detail_order.read().as_ref().map(|o| o.id.clone()) else {
+                                        return;
+                                    };
+                                    let was_archived = archived_ids.read().contains(&order_id);
+                                    spawn(async move {
+                                        let result = if was_archived {
+                                            api::unarchive_order(order_id.clone()).await
+                                        } else {
+                                            api::archive_order(order_id.clone()).await
+                                        };
+                                        match result {
+                                            Ok(()) => {
+                                                if was_archived {
+                                                    archived_ids.write().retain(|id| id != &order_id);
+                                                } else {
+                                                    archived_ids.write().push(order_id);
+                                                }
+                                            }
+                                            Err(e) => log::app_log("ERROR", format!("Archive toggle failed: {}", e)),
+                                        }
+                                    });
+                                },
+                                on_set_due: move |due: Option<DateTime<Utc>>| {
+                                    let Some(order_id) = detail_order.read().as_ref().map(|o| o.id.clone()) else {
+                                        return;
+                                    };
+                                    spawn(async move {
+                                        let result = match due {
+                                            Some(due) => api::set_due_date_override(order_id.clone(), due).await,
+                                            None => api::clear_due_date_override(order_id.clone()).await,
+                                        };
+                                        if let Err(e) = result {
+                                            log::app_log("ERROR", format!("Due date override failed: {}", e));
+                                            return;
+                                        }
+                                        let update = |o: &mut Order| match due {
+                                            Some(due) => o.apply_due_date_override(due),
+                                            None => o.clear_due_date_override(),
+                                        };
+                                        if let Some(o) = orders.write().iter_mut().find(|o| o.id == order_id) {
+                                            update(o);
+                                        }
+                                        if let Some(o) = detail_order.write().as_mut() {
+                                            update(o);
+                                        }
+                                    });
+                                },
+                                on_mark_fulfilled: move |location: Option<String>| {
+                                    let Some(order_id) = detail_order.read().as_ref().map(|o| o.id.clone()) else {
+                                        return;
+                                    };
+                                    spawn(async move {
+                                        let still_open = match api::mark_shopify_fulfilled(order_id.clone(), location).await {
+                                            Ok(n) => n,
+                                            Err(e) => {
+                                                log::app_log("ERROR", format!("Mark fulfilled failed: {}", e));
+                                                return;
+                                            }
+                                        };
+                                        // One location of a split order can leave other locations open.
+                                        let status = if still_open > 0 {
+                                            OrderStatus::PartiallyFulfilled
+                                        } else {
+                                            OrderStatus::Fulfilled
+                                        };
+                                        let update = |o: &mut Order| o.status = status;
+                                        if let Some(o) = orders.write().iter_mut().find(|o| o.id == order_id) {
+                                            update(o);
+                                        }
+                                        if let Some(o) = detail_order.write().as_mut() {
+                                            update(o);
+                                        }
+                                    });
+                                },
                                 on_close: move |_| detail_order.set(None)
                             }
                         }
@@ -344,13 +1215,75 @@ fn App() -> Element {
             }
 
             div { class: "container px-6 py-6",
+                {match etsy_token_status() {
+                    Some(EtsyTokenStatus::ExpiringSoon { days_left }) => rsx! {
+                        div { class: "card-cosmic p-4 mb-6 border-comet-gold flex items-center justify-between gap-3",
+                            p { class: "text-comet-gold text-sm",
+                                "Etsy connection runs out in {days_left} days unless orders are refreshed. Reconnect to be safe."
+                            }
+                            button { class: "btn-cosmic text-sm", onclick: move |_| open_settings(), "Reconnect Etsy" }
+                        }
+                    },
+                    Some(EtsyTokenStatus::NeedsReconnect { reason }) => rsx! {
+                        div { class: "card-cosmic p-4 mb-6 border-warning-red flex items-center justify-between gap-3",
+                            div {
+                                p { class: "text-warning-red text-sm font-semibold", "Etsy needs to be reconnected" }
+                                p { class: "text-stardust text-xs mt-1", "{reason}" }
+                            }
+                            button { class: "btn-nebula text-sm", onclick: move |_| open_settings(), "Reconnect Etsy" }
+                        }
+                    },
+                    _ => rsx! { },
+                }}
+                div { class: "stats-grid mb-2",
+                    {
+                        let (item_count, by_metal) = item_totals();
+                        let breakdown = by_metal
+                            .iter()
+                            .map(|(metal, qty)| format!("{} {}", qty, metal))
+                            .collect::<Vec<_>>()
+                            .join(" · ");
+                        rsx! {
+                            StatCard {
+                                label: "Total items",
+                                value: item_count.to_string(),
+                                subtext: (!breakdown.is_empty()).then_some(breakdown),
+                            }
+                        }
+                    }
+                    {
+                        let totals = *queue_totals.read();
+                        let unmatched = (totals.unmatched > 0).then(|| format!("{} unmatched", totals.unmatched));
+                        rsx! {
+                            StatCard {
+                                label: "Total weight (filtered)",
+                                value: weight_unit.read().format(totals.weight_g),
+                                subtext: unmatched.clone(),
+                            }
+                            StatCard {
+                                label: "Est. material + wax cost (filtered)",
+                                value: format_money(totals.cost_usd, &cost_currency.read()),
+                                subtext: unmatched,
+                            }
+                        }
+                    }
+                }
+                label { class: "flex items-center gap-2 text-stardust text-sm mb-6",
+                    title: "Off: totals only count items that still need to be made",
+                    input {
+                        r#type: "checkbox",
+                        checked: *totals_include_shipped.read(),
+                        onchange: move |evt| totals_include_shipped.set(evt.checked())
+                    }
+                    "Include shipped items in totals"
+                }
                 div { class: "card-cosmic p-6 mb-6",
                     div { class: "flex flex-wrap items-center gap-4",
                         div { class: "flex-1 min-w-0",
                             input {
                                 r#type: "search",
                                 class: "w-full",
-                                placeholder: "Search orders, customers, products...",
+                                placeholder: "Search orders, customers, products, cities, countries... or totals: >500, <100, 100-300",
                                 value: "{search_query}",
                                 oninput: move |evt| search_query.set(evt.value())
                             }
@@ -376,6 +1309,133 @@ fn App() -> Element {
                                 active: *view_filter.read() == ViewFilter::Urgent,
                                 onclick: move |_| view_filter.set(ViewFilter::Urgent)
                             }
+                            FilterButton {
+                                label: "Archived",
+                                active: *view_filter.read() == ViewFilter::Archived,
+                                onclick: move |_| view_filter.set(ViewFilter::Archived)
+                            }
+                        }
+                        {if currencies.read().len() > 1 {
+                            rsx! {
+                                div { class: "flex items-center gap-2",
+                                    span { class: "text-stardust text-sm", "Currency:" }
+                                    select {
+                                        class: "bg-nebula-dark border border-nebula-purple rounded-lg px-3 py-2",
+                                        onchange: move |evt| {
+                                            let value = evt.value();
+                                            currency_filter.set(if value.is_empty() { None } else { Some(value) });
+                                        },
+                                        option { value: "", selected: currency_filter.read().is_none(), "All" }
+                                        for code in currencies.read().iter() {
+                                            option {
+                                                value: "{code}",
+                                                selected: currency_filter.read().as_deref() == Some(code.as_str()),
+                                                "{code}"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        } else {
+                            rsx! { }
+                        }}
+                        {if locations.read().len() > 1 {
+                            rsx! {
+                                div { class: "flex items-center gap-2",
+                                    span { class: "text-stardust text-sm", "Location:" }
+                                    select {
+                                        class: "bg-nebula-dark border border-nebula-purple rounded-lg px-3 py-2",
+                                        onchange: move |evt| {
+                                            let value = evt.value();
+                                            location_filter.set(if value.is_empty() { None } else { Some(value) });
+                                        },
+                                        option { value: "", selected: location_filter.read().is_none(), "All" }
+                                        for name in locations.read().iter() {
+                                            option {
+                                                value: "{name}",
+                                                selected: location_filter.read().as_deref() == Some(name.as_str()),
+                                                "{name}"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        } else {
+                            rsx! { }
+                        }}
+                        {if *empty_order_count.read() > 0 {
+                            rsx! {
+                                label { class: "flex items-center gap-2 text-stardust text-sm",
+                                    title: "Refunded or tip-only orders with no line items",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: *show_empty_orders.read(),
+                                        onchange: move |evt| show_empty_orders.set(evt.checked())
+                                    }
+                                    "Show {empty_order_count} without items"
+                                }
+                            }
+                        } else {
+                            rsx! { }
+                        }}
+                        {if *test_order_count.read() > 0 {
+                            rsx! {
+                                label { class: "flex items-center gap-2 text-stardust text-sm",
+                                    title: "Shopify test orders (placed with a test payment gateway)",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: *show_test_orders.read(),
+                                        onchange: move |evt| show_test_orders.set(evt.checked())
+                                    }
+                                    "Show {test_order_count} test"
+                                }
+                            }
+                        } else {
+                            rsx! { }
+                        }}
+                        div { class: "flex items-center gap-2",
+                            span { class: "text-stardust text-sm", "Status:" }
+                            select {
+                                class: "bg-nebula-dark border border-nebula-purple rounded-lg px-3 py-2",
+                                onchange: move |evt| status_filter.set(OrderStatus::from_label(&evt.value())),
+                                option { value: "", selected: status_filter.read().is_none(), "Any" }
+                                for status in OrderStatus::ALL {
+                                    option {
+                                        value: "{status.label()}",
+                                        selected: *status_filter.read() == Some(status),
+                                        "{status.label()}"
+                                    }
+                                }
+                            }
+                        }
+                        {if status_filter.read().is_none() && *inactive_count.read() > 0 {
+                            rsx! {
+                                label { class: "flex items-center gap-2 text-stardust text-sm",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: *show_cancelled.read(),
+                                        onchange: move |evt| show_cancelled.set(evt.checked())
+                                    }
+                                    "Show {inactive_count} cancelled/refunded"
+                                }
+                            }
+                        } else {
+                            rsx! { }
+                        }}
+                        div { class: "flex items-center gap-2",
+                            span { class: "text-stardust text-sm", "Weight:" }
+                            select {
+                                class: "bg-nebula-dark border border-nebula-purple rounded-lg px-3 py-2",
+                                onchange: move |evt| {
+                                    match evt.value().as_str() {
+                                        "g" => weight_unit.set(WeightUnit::Grams),
+                                        "ozt" => weight_unit.set(WeightUnit::TroyOunces),
+                                        _ => {}
+                                    }
+                                },
+                                option { value: "g", selected: *weight_unit.read() == WeightUnit::Grams, "Grams" }
+                                option { value: "ozt", selected: *weight_unit.read() == WeightUnit::TroyOunces, "Troy oz" }
+                            }
                         }
                         div { class: "flex items-center gap-2",
                             span { class: "text-stardust text-sm", "Sort by:" }
@@ -389,9 +1449,9 @@ fn App() -> Element {
                                         _ => {}
                                     }
                                 },
-                                option { value: "due", "Due Date" }
-                                option { value: "order", "Order Date" }
-                                option { value: "customer", "Customer" }
+                                option { value: "due", selected: *sort_by.read() == SortBy::DueDate, "Due Date" }
+                                option { value: "order", selected: *sort_by.read() == SortBy::OrderDate, "Order Date" }
+                                option { value: "customer", selected: *sort_by.read() == SortBy::Customer, "Customer" }
                             }
                         }
                     }
@@ -410,7 +1470,39 @@ fn App() -> Element {
                             p { class: "text-stardust mt-4", "No orders found" }
                         }
                     } else {
-                        div { class: "overflow-x-auto",
+                        // Key handling lives on the table container (not the window) so typing in
+                        // the search box never moves the selection.
+                        div {
+                            class: "overflow-x-auto orders-table-nav",
+                            tabindex: "0",
+                            onkeydown: move |evt: KeyboardEvent| {
+                                let len = filtered_orders.read().len();
+                                if len == 0 {
+                                    return;
+                                }
+                                match evt.key() {
+                                    Key::ArrowDown => {
+                                        evt.prevent_default();
+                                        let next = focused_row().map(|i| (i + 1).min(len - 1)).unwrap_or(current_page() * page_size());
+                                        focused_row.set(Some(next));
+                                        current_page.set(next / page_size());
+                                    }
+                                    Key::ArrowUp => {
+                                        evt.prevent_default();
+                                        let prev = focused_row().map(|i| i.saturating_sub(1)).unwrap_or(current_page() * page_size());
+                                        focused_row.set(Some(prev));
+                                        current_page.set(prev / page_size());
+                                    }
+                                    Key::Enter => {
+                                        if let Some(order) = focused_row().and_then(|i| filtered_orders.read().get(i).cloned()) {
+                                            detail_order.set(Some(order));
+                                        }
+                                    }
+                                    // Closing the detail dialog is handled app-wide.
+                                    Key::Escape if detail_order.peek().is_none() => focused_row.set(None),
+                                    _ => {}
+                                }
+                            },
                             table { class: "table-cosmic table-orders",
                                 thead {
                                     tr {
@@ -429,16 +1521,69 @@ fn App() -> Element {
                                     }
                                 }
                                 tbody {
-                                    for (order, order_for_click) in orders_for_table.read().clone() {
+                                    for (idx, order, order_for_click) in orders_for_table.read().clone() {
                                         OrderRow {
                                             order,
                                             piece_costs: piece_costs_cache.read().clone(),
-                                            on_click: move |_| detail_order.set(Some(order_for_click.clone())),
+                                            tz: display_tz(),
+                                            thresholds: due_thresholds(),
+                                            cost_currency: cost_currency(),
+                                            focused: *focused_row.read() == Some(idx),
+                                            on_click: move |_| {
+                                                focused_row.set(Some(idx));
+                                                detail_order.set(Some(order_for_click.clone()));
+                                            },
                                         }
                                     }
                                 }
                             }
                         }
+                        {{
+                            let total = filtered_orders.read().len();
+                            let first = current_page() * page_size() + 1;
+                            let last = (current_page() * page_size() + page_size()).min(total);
+                            rsx! {
+                                div { class: "table-pager",
+                                    span { class: "text-stardust text-sm", "{first}–{last} of {total}" }
+                                    div { class: "flex items-center gap-2",
+                                        button {
+                                            class: "btn-cosmic text-sm",
+                                            disabled: current_page() == 0,
+                                            onclick: move |_| {
+                                                focused_row.set(None);
+                                                current_page.set(current_page().saturating_sub(1));
+                                            },
+                                            "Prev"
+                                        }
+                                        span { class: "text-stardust text-sm", "Page {current_page() + 1} of {page_count}" }
+                                        button {
+                                            class: "btn-cosmic text-sm",
+                                            disabled: current_page() + 1 >= page_count(),
+                                            onclick: move |_| {
+                                                focused_row.set(None);
+                                                current_page.set((current_page() + 1).min(page_count() - 1));
+                                            },
+                                            "Next"
+                                        }
+                                    }
+                                    div { class: "flex items-center gap-2",
+                                        span { class: "text-stardust text-sm", "Rows:" }
+                                        select {
+                                            class: "bg-nebula-dark border border-nebula-purple rounded-lg px-3 py-2",
+                                            onchange: move |evt| {
+                                                if let Ok(size) = evt.value().parse::<usize>() {
+                                                    focused_row.set(None);
+                                                    page_size.set(size.max(1));
+                                                }
+                                            },
+                                            for size in PAGE_SIZE_OPTIONS {
+                                                option { value: "{size}", selected: page_size() == size, "{size}" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }}
                     }
                 }
 
@@ -448,6 +1593,12 @@ fn App() -> Element {
                             div { class: "flex items-center gap-3",
                                 p { class: "text-warning-red", "{err}" }
                             }
+                            if needs_setup() {
+                                p { class: "text-stardust text-sm mt-2",
+                                    "Add the missing values to the server's .env file (or the Home Assistant add-on options) and restart. "
+                                    "Sources you don't use can be turned off in Settings."
+                                }
+                            }
                         }
                     }
                 } else {
@@ -458,6 +1609,131 @@ fn App() -> Element {
     }
 }
 
+#[component]
+fn StatCard(label: String, value: String, subtext: Option<String>) -> Element {
+    rsx! {
+        div { class: "card-cosmic p-6",
+            div { class: "stat-value", "{value}" }
+            div { class: "stat-label", "{label}" }
+            {subtext.map(|t| rsx! { div { class: "text-xs text-stardust mt-1", "{t}" } })}
+        }
+    }
+}
+
+#[component]
+fn UnmatchedProductsTable(products: Vec<UnmatchedProduct>) -> Element {
+    if products.is_empty() {
+        return rsx! {
+            p { class: "text-stardust text-sm py-6 text-center", "Every item has a cost match." }
+        };
+    }
+    rsx! {
+        div { class: "flex-1 overflow-y-auto mt-3",
+            table { class: "table-cosmic",
+                thead {
+                    tr {
+                        th { "Product" }
+                        th { "Variant" }
+                        th { "Metal" }
+                        th { "Size" }
+                        th { title: "Line items across current orders", "Count" }
+                    }
+                }
+                tbody {
+                    for product in products {
+                        tr {
+                            td { class: "text-star-white", "{product.name}" }
+                            td { class: "text-stardust", {product.variant_info.unwrap_or_default()} }
+                            td { "{product.metal}" }
+                            td { class: "font-mono", {product.ring_size.unwrap_or_default()} }
+                            td { class: "font-semibold", "{product.count}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Suggested casting batches, then the pieces that have no weight to plan with.
+#[component]
+fn CastingPlanView(plan: CastingPlan, weight_unit: WeightUnit) -> Element {
+    if plan.batches.is_empty() && plan.needs_data.is_empty() {
+        return rsx! {
+            p { class: "text-stardust text-sm py-6 text-center", "Nothing of this metal is due in that window." }
+        };
+    }
+    let CastingPlan { batches, needs_data } = plan;
+    let needs_data_count = needs_data.len();
+    rsx! {
+        div { class: "flex-1 overflow-y-auto mt-3 space-y-4",
+            for (idx, batch) in batches.into_iter().enumerate() {
+                div {
+                    p { class: "text-star-white font-semibold text-sm mb-1",
+                        {format!("Batch {} – {}", idx + 1, weight_unit.format(batch.total_weight_g))}
+                    }
+                    table { class: "table-cosmic",
+                        tbody {
+                            for piece in batch.pieces {
+                                tr {
+                                    td { class: "font-semibold", "{piece.quantity}x" }
+                                    td { class: "text-star-white", "{piece.item_name}" }
+                                    td { class: "font-mono", {piece.ring_size.unwrap_or_default()} }
+                                    td { class: "text-stardust", "{piece.order_number}" }
+                                    td { class: "text-stardust", {weight_unit.format(piece.weight_g)} }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if needs_data_count > 0 {
+                div {
+                    p { class: "match-missing text-sm mb-1", "Needs weight data ({needs_data_count})" }
+                    table { class: "table-cosmic",
+                        tbody {
+                            for piece in needs_data {
+                                tr {
+                                    td { class: "font-semibold", "{piece.quantity}x" }
+                                    td { class: "text-star-white", "{piece.item_name}" }
+                                    td { class: "font-mono", {piece.ring_size.unwrap_or_default()} }
+                                    td { class: "text-stardust", "{piece.order_number}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Small "Copy" button that shows "Copied" for a moment after a successful copy.
+#[component]
+fn CopyButton(text: String, title: String) -> Element {
+    let mut copied = use_signal(|| false);
+    rsx! {
+        button {
+            class: "btn-cosmic text-xs",
+            title: "{title}",
+            onclick: move |_| {
+                let text = text.clone();
+                spawn(async move {
+                    match copy_to_clipboard(&text).await {
+                        Ok(()) => {
+                            copied.set(true);
+                            let _ = document::eval("await new Promise(r => setTimeout(r, 1500)); return true;").await;
+                            copied.set(false);
+                        }
+                        Err(e) => log::app_log("ERROR", format!("Copy failed: {}", e)),
+                    }
+                });
+            },
+            if copied() { "Copied" } else { "Copy" }
+        }
+    }
+}
+
 #[component]
 fn FilterButton(label: String, active: bool, onclick: EventHandler<MouseEvent>) -> Element {
     let class = if active { "btn-nebula" } else { "btn-cosmic" };
@@ -470,14 +1746,38 @@ fn FilterButton(label: String, active: bool, onclick: EventHandler<MouseEvent>)
     }
 }
 
+#[component]
+fn SettingsToggle(label: String, checked: bool, onchange: EventHandler<bool>) -> Element {
+    rsx! {
+        div { class: "flex items-center justify-between gap-3",
+            span { class: "text-moonlight text-sm", "{label}" }
+            label { class: "toggle-cosmic",
+                input {
+                    r#type: "checkbox",
+                    checked,
+                    onchange: move |evt| onchange.call(evt.checked())
+                }
+                span { class: "toggle-slider" }
+            }
+        }
+    }
+}
+
+/// Line items listed in a collapsed row before "+N more".
+const ROW_ITEMS_SHOWN: usize = 2;
+
 #[component]
 fn OrderRow(
     order: Order,
     piece_costs: Vec<PieceCostRow>,
+    tz: Tz,
+    thresholds: DueThresholds,
+    cost_currency: String,
+    focused: bool,
     on_click: EventHandler<MouseEvent>,
 ) -> Element {
-    let days_left = order.days_until_due();
-    let urgency_class = order.urgency_class();
+    let days_left = order.days_until_due(tz);
+    let urgency_class = order.urgency_class(tz, thresholds);
     let days_display = if days_left < 0 {
         format!("{} overdue", days_left.abs())
     } else if days_left == 0 {
@@ -491,16 +1791,20 @@ fn OrderRow(
         OrderSource::Shopify => ("Shopify", "badge-method"),
         OrderSource::Etsy => ("Etsy", "badge-nebula"),
     };
-    let primary_metal = order
+    let (metal_name, metal_class) = order
         .items
         .first()
-        .map(|i| i.metal_type.clone())
-        .unwrap_or(MetalType::Unknown);
-    let ring_size = order
-        .items
-        .iter()
-        .find_map(|i| i.ring_size.clone())
-        .unwrap_or_else(|| "N/A".to_string());
+        .map(|i| (i.metal_label(), i.metal_badge_class()))
+        .unwrap_or_else(|| (MetalType::Unknown.display_name().to_string(), MetalType::Unknown.display_class()));
+    let ring_size = if order.has_items() {
+        order
+            .items
+            .iter()
+            .find_map(|i| i.ring_size.clone())
+            .unwrap_or_else(|| "N/A".to_string())
+    } else {
+        "\u{2014}".to_string()
+    };
     let items_display: Vec<String> = order
         .items
         .iter()
@@ -513,18 +1817,50 @@ fn OrderRow(
         })
         .collect();
     let items_tooltip = items_display.join("\n");
+    // Rows aren't keyed, so remember which order was expanded rather than a bare flag.
+    let mut expanded_order = use_signal(|| None::<String>);
+    let items_expanded = expanded_order.read().as_deref() == Some(order.id.as_str());
+    let hidden_items = if items_expanded {
+        0
+    } else {
+        items_display.len().saturating_sub(ROW_ITEMS_SHOWN)
+    };
+    let item_count = order.item_count();
     let first_image = order.items.first().and_then(|i| i.image_url.clone());
 
-    let (order_cost, order_weight) = order.items.iter().fold((0.0_f64, 0.0_f64), |(c, w), item| {
-        let cw = lookup_piece_cost(item, &piece_costs);
+    let matches: Vec<Option<PieceCostMatch>> = order
+        .items
+        .iter()
+        .map(|item| lookup_piece_cost_match(item, &piece_costs))
+        .collect();
+    let (order_cost, order_weight) = order.items.iter().zip(&matches).fold((0.0_f64, 0.0_f64), |(c, w), (item, m)| {
         let q = item.quantity as f64;
         (
-            c + cw.as_ref().map(|x| x.cost_usd * q).unwrap_or(0.0),
-            w + cw.as_ref().map(|x| x.weight_g * q).unwrap_or(0.0),
+            c + m.as_ref().map(|x| x.cost_weight.cost_usd * q).unwrap_or(0.0),
+            w + m.as_ref().map(|x| x.cost_weight.weight_g * q).unwrap_or(0.0),
         )
     });
+    // Audit trail for the cost column: which catalog row each item matched, and how.
+    let cost_tooltip = order
+        .items
+        .iter()
+        .zip(&matches)
+        .map(|(item, m)| match m {
+            Some(m) => format!("{} \u{2192} {} ({})", item.name, m.design_key, m.strategy.label()),
+            None => format!("{} \u{2192} no match", item.name),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let weakest_fuzzy = matches
+        .iter()
+        .flatten()
+        .filter_map(|m| match m.strategy {
+            MatchStrategy::Fuzzy { score } => Some(score),
+            _ => None,
+        })
+        .fold(None, |acc: Option<f64>, score| Some(acc.map_or(score, |a| a.min(score))));
     let cost_str = if order_cost > 0.0 {
-        format!("$ {:.2}", order_cost)
+        format_money(order_cost, &cost_currency)
     } else {
         "\u{2014}".to_string()
     };
@@ -537,6 +1873,8 @@ fn OrderRow(
     rsx! {
         tr {
             class: "{urgency_class} order-row-clickable",
+            class: if focused { "order-row-focused" },
+            aria_selected: if focused { "true" } else { "false" },
             onclick: move |evt| on_click.call(evt),
             td { class: "td-thumb",
                 {match first_image.as_deref() {
@@ -545,39 +1883,87 @@ fn OrderRow(
                 }}
             }
             td { class: "td-nowrap",
-                div { class: "font-semibold text-star-white", "{order.order_number}" }
+                div { class: "font-semibold text-star-white",
+                    {match order.admin_url.as_ref() {
+                        Some(url) => rsx! {
+                            a {
+                                class: "order-admin-link",
+                                href: "{url}",
+                                target: "_blank",
+                                rel: "noopener noreferrer",
+                                title: "Open in {order.source.display_name()}",
+                                onclick: move |evt: MouseEvent| evt.stop_propagation(),
+                                onkeydown: keep_enter_local,
+                                "{order.order_number}"
+                            }
+                        },
+                        None => rsx! { "{order.order_number}" },
+                    }}
+                }
                 div { class: "text-xs text-stardust",
-                    "{order.order_date.format(\"%b %d, %Y\")}"
+                    "{order.order_date.with_timezone(&tz).format(\"%b %d, %Y\")}"
                 }
+                span { class: "badge badge-status {order.status_badge_class()}", "{order.status_label()}" }
+                {order.is_test.then(|| rsx! {
+                    span { class: "badge badge-status badge-test", title: "Shopify test order", "TEST" }
+                })}
+                {(item_count > 1).then(|| rsx! {
+                    span { class: "badge badge-status badge-nebula", "{item_count} items" }
+                })}
+                {order.spans_multiple_locations().then(|| rsx! {
+                    span {
+                        class: "badge badge-status badge-method",
+                        title: "Ships from: {order.item_locations().join(\", \")}",
+                        "multi-location"
+                    }
+                })}
             }
             td { class: "td-nowrap text-moonlight", title: "{order.customer_name}",
                 span { class: "cell-truncate", "{order.customer_name}" }
             }
             td { class: "td-items", title: "{items_tooltip}",
                 div { class: "items-cell cell-truncate",
-                    for (idx, item) in items_display.iter().enumerate() {
+                    if !order.has_items() {
+                        div { class: "text-sm text-comet-gold", "No items" }
+                    }
+                    for (idx, item) in items_display.iter().enumerate().take(items_display.len() - hidden_items) {
                         div {
                             class: "text-sm",
                             class: if idx > 0 { "text-stardust" } else { "text-star-white" },
                             "{item}"
                         }
                     }
+                    if hidden_items > 0 {
+                        button {
+                            class: "items-more",
+                            onclick: {
+                                let id = order.id.clone();
+                                move |evt: MouseEvent| {
+                                    evt.stop_propagation();
+                                    expanded_order.set(Some(id.clone()));
+                                }
+                            },
+                            onkeydown: keep_enter_local,
+                            "+{hidden_items} more"
+                        }
+                    }
                 }
             }
             td { class: "td-nowrap",
-                {
-                    let badge_class = format!("badge {}", primary_metal.display_class());
-                    let metal_name = primary_metal.display_name();
-                    rsx! {
-                        span { class: "{badge_class}", "{metal_name}" }
-                    }
+                if order.has_items() {
+                    span { class: "badge {metal_class}", "{metal_name}" }
+                } else {
+                    span { class: "text-stardust", "\u{2014}" }
                 }
             }
             td { class: "td-nowrap",
                 span { class: "font-mono text-aurora-purple", "{ring_size}" }
             }
             td { class: "td-nowrap text-moonlight",
-                "{order.due_date.format(\"%b %d\")}"
+                "{order.due_date.with_timezone(&tz).format(\"%b %d\")}"
+                {order.has_due_date_override().then(|| rsx! {
+                    span { class: "text-xs text-comet-gold ml-1", title: "Due date manually set", "edited" }
+                })}
             }
             td { class: "td-nowrap",
                 {
@@ -587,15 +1973,28 @@ fn OrderRow(
                         "urgency-warning" => "font-bold text-comet-gold",
                         _ => "font-bold text-alien-green",
                     };
+                    let consumed = order.lead_time_consumed_pct(tz);
+                    let bar_class = if consumed > 100.0 { "lead-bar-fill lead-bar-over" } else { "lead-bar-fill" };
+                    let bar_width = consumed.clamp(0.0, 100.0);
                     rsx! {
                         span { class: "{text_color}", "{days_display}" }
+                        div {
+                            class: "lead-bar",
+                            title: "{consumed:.0}% of lead time used ({order.age_days(tz)} days in production)",
+                            div { class: "{bar_class}", style: "width: {bar_width:.0}%" }
+                        }
                     }
                 }
             }
             td { class: "td-nowrap text-star-white font-semibold",
-                {format!("$ {:.2}", order.total_price)}
+                {format_money(order.total_price, &order.currency)}
+            }
+            td { class: "td-nowrap text-stardust", title: "{cost_tooltip}",
+                "{cost_str}"
+                {weakest_fuzzy.map(|score| rsx! {
+                    span { class: "match-fuzzy ml-1", {format!("~{:.0}%", score * 100.0)} }
+                })}
             }
-            td { class: "td-nowrap text-stardust", title: "Our cost (from catalog)", "{cost_str}" }
             td { class: "td-nowrap text-stardust", title: "Weight (g)", "{weight_str}" }
             td { class: "td-nowrap",
                 {
@@ -614,13 +2013,22 @@ fn OrderRow(
 fn OrderDetailDialog(
     order: Order,
     piece_costs: Vec<PieceCostRow>,
+    weight_unit: WeightUnit,
+    tz: Tz,
+    cost_currency: String,
+    archived: bool,
+    note: String,
+    on_save_note: EventHandler<String>,
+    on_toggle_archive: EventHandler<MouseEvent>,
+    on_set_due: EventHandler<Option<DateTime<Utc>>>,
+    on_mark_fulfilled: EventHandler<Option<String>>,
     on_close: EventHandler<MouseEvent>,
 ) -> Element {
     let source_label = match order.source {
         OrderSource::Shopify => "Shopify",
         OrderSource::Etsy => "Etsy",
     };
-    let days_left = order.days_until_due();
+    let days_left = order.days_until_due(tz);
     let days_display = if days_left < 0 {
         format!("{} days overdue", days_left.abs())
     } else if days_left == 0 {
@@ -630,7 +2038,7 @@ fn OrderDetailDialog(
     } else {
         format!("{} days left", days_left)
     };
-    let total_str = format!("{} {:.2}", order.currency, order.total_price);
+    let total_str = format_money(order.total_price, &order.currency);
 
     rsx! {
         div { class: "flex items-center justify-between mb-4",
@@ -639,6 +2047,12 @@ fn OrderDetailDialog(
             }
             div { class: "flex items-center gap-2",
                 span { class: "badge badge-nebula", "{source_label}" }
+                button {
+                    class: "btn-cosmic text-sm",
+                    title: if archived { "Return to the active views" } else { "Hide from the active views (e.g. on hold)" },
+                    onclick: move |evt| on_toggle_archive.call(evt),
+                    if archived { "Unarchive" } else { "Archive" }
+                }
                 button {
                     class: "btn-cosmic text-sm",
                     onclick: move |evt| on_close.call(evt),
@@ -658,11 +2072,33 @@ fn OrderDetailDialog(
             dt { "Customer" }
             dd { "{order.customer_name}" }
             dt { "Order date" }
-            dd { "{order.order_date.format(\"%b %d, %Y\")}" }
+            dd {
+                "{order.order_date.with_timezone(&tz).format(\"%b %d, %Y\")}"
+                {order.raw_order_date.map(|raw| rsx! {
+                    span {
+                        class: "badge badge-nebula ml-2",
+                        title: "The source reported a future date: {raw.with_timezone(&tz).format(\"%b %d, %Y %H:%M %Z\")}",
+                        "clamped"
+                    }
+                })}
+            }
             dt { "Ship by / Due" }
-            dd { "{order.due_date.format(\"%b %d, %Y\")} ({days_display})" }
+            dd {
+                "{order.due_date.with_timezone(&tz).format(\"%b %d, %Y\")} ({days_display})"
+                {order.computed_due_date.map(|computed| rsx! {
+                    span {
+                        class: "badge badge-nebula ml-2",
+                        title: "Manually set. Computed: {computed.with_timezone(&tz).format(\"%b %d, %Y\")}",
+                        "edited"
+                    }
+                })}
+            }
             dt { "Status" }
-            dd { "{order.status}" }
+            dd { "{order.status_label()}" }
+            {order.location.as_ref().map(|loc| rsx! {
+                dt { "Location" }
+                dd { "{loc}" }
+            })}
             dt { "Total" }
             dd { class: "font-semibold text-star-white", "{total_str}" }
         }
@@ -674,7 +2110,7 @@ fn OrderDetailDialog(
                 })
                 .sum();
             if order_cost > 0.0 {
-                let s = format!("$ {:.2}", order_cost);
+                let s = format_money(order_cost, &cost_currency);
                 rsx! {
                     dt { "Our cost" }
                     dd { class: "font-semibold text-aurora-purple", "{s}" }
@@ -685,17 +2121,63 @@ fn OrderDetailDialog(
         }}
         {order.shipping_address.as_ref().map(|addr| rsx! {
             div { class: "mt-4",
-                p { class: "text-stardust text-sm font-medium mb-1", "Shipping address" }
-                p { class: "text-moonlight text-sm", "{addr}" }
+                div { class: "flex items-center gap-2 mb-1",
+                    p { class: "text-stardust text-sm font-medium", "Shipping address" }
+                    CopyButton { text: addr.label_text(), title: "Copy as a multi-line shipping label" }
+                }
+                p { class: "text-moonlight text-sm", "{addr.single_line()}" }
             }
         })}
+        {(order.source == OrderSource::Shopify
+            && matches!(order.status, OrderStatus::Unfulfilled | OrderStatus::PartiallyFulfilled))
+            .then(|| {
+                // Split orders get one button per location so each can ship on its own.
+                let locations: Vec<String> = if order.spans_multiple_locations() {
+                    order.item_locations().into_iter().map(str::to_string).collect()
+                } else {
+                    Vec::new()
+                };
+                rsx! {
+                    div { class: "mt-4 flex items-center gap-2",
+                        if locations.is_empty() {
+                            button {
+                                class: "btn-nebula text-sm",
+                                onclick: move |_| on_mark_fulfilled.call(None),
+                                "Mark fulfilled"
+                            }
+                        }
+                        for loc in locations {
+                            button {
+                                class: "btn-nebula text-sm",
+                                onclick: {
+                                    let loc = loc.clone();
+                                    move |_| on_mark_fulfilled.call(Some(loc.clone()))
+                                },
+                                "Fulfill from {loc}"
+                            }
+                        }
+                    }
+                }
+            })}
+        DueDateEditor {
+            key: "{order.id}",
+            due_date: order.due_date,
+            tz,
+            overridden: order.has_due_date_override(),
+            on_change: on_set_due,
+        }
+        OrderNoteEditor { key: "{order.id}", note, on_save: on_save_note }
         div { class: "mt-4",
             p { class: "text-stardust text-sm font-medium mb-2", "Items" }
             div { class: "space-y-3",
                 for item in order.items.iter() {
                     OrderDetailItemRow {
                         item: item.clone(),
-                        cost_weight: lookup_piece_cost(item, &piece_costs),
+                        currency: order.currency.clone(),
+                        cost_currency: cost_currency.clone(),
+                        cost_match: lookup_piece_cost_match(item, &piece_costs),
+                        costs_loaded: !piece_costs.is_empty(),
+                        weight_unit,
                     }
                 }
             }
@@ -703,15 +2185,109 @@ fn OrderDetailDialog(
     }
 }
 
+/// Date picker for a manual due date (`None` = revert to the computed date).
+#[component]
+fn DueDateEditor(
+    due_date: DateTime<Utc>,
+    tz: Tz,
+    overridden: bool,
+    on_change: EventHandler<Option<DateTime<Utc>>>,
+) -> Element {
+    let mut draft = use_signal(|| due_date.with_timezone(&tz).format("%Y-%m-%d").to_string());
+    rsx! {
+        div { class: "mt-4",
+            p { class: "text-stardust text-sm font-medium mb-1", "Reschedule" }
+            div { class: "flex items-center gap-2",
+                input {
+                    r#type: "date",
+                    class: "bg-nebula-dark border border-nebula-purple rounded-lg px-3 py-2 text-star-white text-sm",
+                    value: "{draft}",
+                    oninput: move |evt| draft.set(evt.value())
+                }
+                button {
+                    class: "btn-nebula text-sm",
+                    onclick: move |_| {
+                        // End of day in the display zone, so the chosen date still counts as "due today".
+                        let due = chrono::NaiveDate::parse_from_str(&draft.read(), "%Y-%m-%d")
+                            .ok()
+                            .and_then(|d| d.and_hms_opt(23, 59, 59))
+                            .and_then(|dt| dt.and_local_timezone(tz).earliest())
+                            .map(|dt| dt.with_timezone(&Utc));
+                        if let Some(due) = due {
+                            on_change.call(Some(due));
+                        }
+                    },
+                    "Set due date"
+                }
+                {overridden.then(|| rsx! {
+                    button {
+                        class: "btn-cosmic text-sm",
+                        onclick: move |_| on_change.call(None),
+                        "Revert"
+                    }
+                })}
+            }
+        }
+    }
+}
+
+#[component]
+fn OrderNoteEditor(note: String, on_save: EventHandler<String>) -> Element {
+    let mut draft = use_signal(|| note.clone());
+    let dirty = *draft.read() != note;
+    rsx! {
+        div { class: "mt-4",
+            p { class: "text-stardust text-sm font-medium mb-1", "Notes" }
+            textarea {
+                class: "w-full bg-nebula-dark border border-nebula-purple rounded-lg px-3 py-2 text-star-white text-sm min-h-[60px]",
+                placeholder: "Internal notes (searchable)...",
+                value: "{draft}",
+                oninput: move |evt| draft.set(evt.value())
+            }
+            button {
+                class: "btn-nebula text-sm mt-2",
+                disabled: !dirty,
+                onclick: move |_| on_save.call(draft.read().clone()),
+                "Save note"
+            }
+        }
+    }
+}
+
 #[component]
-fn OrderDetailItemRow(item: OrderItem, cost_weight: Option<ItemCostWeight>) -> Element {
-    let price_str = format!("${:.2}", item.price);
-    let (cost_str, weight_str) = match &cost_weight {
-        Some(cw) => (
-            format!("${:.2}", cw.cost_usd * item.quantity as f64),
-            format!("{:.1} g", cw.weight_g * item.quantity as f64),
-        ),
-        None => ("\u{2014}".to_string(), "\u{2014}".to_string()),
+fn OrderDetailItemRow(
+    item: OrderItem,
+    currency: String,
+    cost_currency: String,
+    cost_match: Option<PieceCostMatch>,
+    costs_loaded: bool,
+    weight_unit: WeightUnit,
+) -> Element {
+    let price_str = format_money(item.price, &currency);
+    let qty = item.quantity as f64;
+    let cost_line = cost_match.as_ref().map(|m| {
+        let cw = &m.cost_weight;
+        if item.quantity > 1 {
+            format!(
+                "Our cost: {} ({} each) | Weight: {} ({} each)",
+                format_money(cw.cost_usd * qty, &cost_currency),
+                format_money(cw.cost_usd, &cost_currency),
+                weight_unit.format(cw.weight_g * qty),
+                weight_unit.format(cw.weight_g),
+            )
+        } else {
+            format!(
+                "Our cost: {} | Weight: {}",
+                format_money(cw.cost_usd, &cost_currency),
+                weight_unit.format(cw.weight_g)
+            )
+        }
+    });
+    // Tell "this item has no piece_costs row" apart from "piece costs haven't loaded".
+    let no_cost_reason = if costs_loaded {
+        "No cost data: no piece_costs row matches this item"
+    } else {
+        "No cost data: piece costs not loaded"
     };
     rsx! {
         div { class: "flex items-start gap-3 p-3 rounded-lg bg-nebula-dark/50 border border-nebula-purple/20",
@@ -725,11 +2301,51 @@ fn OrderDetailItemRow(item: OrderItem, cost_weight: Option<ItemCostWeight>) -> E
                 {(item.quantity > 1).then(|| rsx! { p { class: "text-stardust text-sm", "Qty: {item.quantity}" } })}
                 {item.variant_info.as_ref().map(|v| rsx! { p { class: "text-stardust text-sm", "{v}" } })}
                 {item.ring_size.as_ref().map(|s| rsx! { p { class: "text-aurora-purple text-sm font-mono", "Size: {s}" } })}
-                p { class: "text-moonlight text-sm", "{item.metal_type.display_name()} | {price_str}" }
-                p { class: "text-stardust text-sm mt-1",
-                    "Our cost: {cost_str} | Weight: {weight_str}"
-                }
+                p { class: "text-moonlight text-sm", "{item.metal_label()} | {price_str}" }
+                {match cost_line {
+                    Some(line) => rsx! { p { class: "text-stardust text-sm mt-1", "{line}" } },
+                    None => rsx! { p { class: "text-sm mt-1 match-missing", "{no_cost_reason}" } },
+                }}
+                {cost_match.as_ref().map(|m| {
+                    let class = if m.strategy.is_fuzzy() { "text-xs match-fuzzy" } else { "text-xs text-stardust" };
+                    rsx! { p { class: "{class}", "Matched {m.design_key} by {m.strategy.label()}" } }
+                })}
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparisons_parse_without_prefix() {
+        assert_eq!(PriceQuery::parse(">500"), Some(PriceQuery::Above(500.0)));
+        assert_eq!(PriceQuery::parse("<= $100"), Some(PriceQuery::AtMost(100.0)));
+    }
+
+    #[test]
+    fn bare_ranges_parse() {
+        assert_eq!(PriceQuery::parse("100-300"), Some(PriceQuery::Between(100.0, 300.0)));
+        assert_eq!(PriceQuery::parse("300 - 100"), Some(PriceQuery::Between(100.0, 300.0)));
+        assert_eq!(PriceQuery::parse("100-"), None);
+    }
+
+    #[test]
+    fn months_and_ring_sizes_are_text() {
+        assert_eq!(PriceQuery::parse("2024-03"), None);
+        assert_eq!(PriceQuery::parse("6-7"), None);
+        assert_eq!(PriceQuery::parse("6.5-8"), None);
+        // Out of month / size range: a price after all.
+        assert_eq!(PriceQuery::parse("2024-13"), Some(PriceQuery::Between(13.0, 2024.0)));
+        assert_eq!(PriceQuery::parse("10-20"), Some(PriceQuery::Between(10.0, 20.0)));
+    }
+
+    #[test]
+    fn prefix_forces_a_price_range() {
+        assert_eq!(PriceQuery::parse("$6-7"), Some(PriceQuery::Between(6.0, 7.0)));
+        assert_eq!(PriceQuery::parse("price: 2024-03"), Some(PriceQuery::Between(3.0, 2024.0)));
+        assert_eq!(PriceQuery::parse("price:abc"), None);
+    }
+}
@@ -1,6 +1,7 @@
 //! Shared domain types for orders (used by UI and by Etsy/Shopify API modules).
 
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "server")]
@@ -15,18 +16,30 @@ pub enum MetalType {
 }
 
 impl MetalType {
-    /// Parse metal type from product name/variant text.
+    /// Parse the primary metal type from product name/variant text.
     pub fn from_string(s: &str) -> Self {
+        Self::detect_all(s).into_iter().next().unwrap_or(MetalType::Unknown)
+    }
+
+    /// All metals mentioned in product name/variant text, primary first (gold, silver, bronze).
+    /// Plated pieces ("gold plated sterling") are a single metal, not two-tone, so only the
+    /// primary is returned when the text mentions plating. Empty when nothing matches.
+    pub fn detect_all(s: &str) -> Vec<Self> {
         let lower = s.to_lowercase();
+        let mut metals = Vec::new();
         if lower.contains("gold") || lower.contains("14k") || lower.contains("18k") || lower.contains("10k") {
-            MetalType::Gold
-        } else if lower.contains("silver") || lower.contains("sterling") || lower.contains("925") {
-            MetalType::Silver
-        } else if lower.contains("bronze") || lower.contains("brass") {
-            MetalType::Bronze
-        } else {
-            MetalType::Unknown
+            metals.push(MetalType::Gold);
+        }
+        if lower.contains("silver") || lower.contains("sterling") || lower.contains("925") {
+            metals.push(MetalType::Silver);
+        }
+        if lower.contains("bronze") || lower.contains("brass") {
+            metals.push(MetalType::Bronze);
         }
+        if lower.contains("plated") || lower.contains("vermeil") {
+            metals.truncate(1);
+        }
+        metals
     }
 
     pub fn display_class(&self) -> &'static str {
@@ -48,12 +61,330 @@ impl MetalType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum OrderSource {
     Shopify,
     Etsy,
 }
 
+impl OrderSource {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            OrderSource::Shopify => "Shopify",
+            OrderSource::Etsy => "Etsy",
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Money formatting
+// ---------------------------------------------------------------------------
+
+/// Default currency of catalog costs in piece_costs (`cost_usd`); see [Settings::base_currency].
+pub const COST_CURRENCY: &str = "USD";
+
+/// Display symbol for an ISO 4217 code; unknown codes come back unchanged.
+pub fn currency_symbol(code: &str) -> &str {
+    match code.trim().to_ascii_uppercase().as_str() {
+        "USD" => "$",
+        "CAD" => "CA$",
+        "AUD" => "A$",
+        "NZD" => "NZ$",
+        "EUR" => "\u{20ac}",
+        "GBP" => "\u{a3}",
+        "JPY" => "\u{a5}",
+        "INR" => "\u{20b9}",
+        _ => code,
+    }
+}
+
+/// Format an amount for display, e.g. "$12.50", "€8.00", or "CHF 40.00" for codes
+/// without a symbol. Zero-decimal currencies (JPY) are shown without cents.
+pub fn format_money(amount: f64, code: &str) -> String {
+    let symbol = currency_symbol(code);
+    let decimals = if code.trim().eq_ignore_ascii_case("JPY") { 0 } else { 2 };
+    if symbol == code {
+        format!("{} {:.*}", code, decimals, amount)
+    } else {
+        format!("{}{:.*}", symbol, decimals, amount)
+    }
+}
+
+/// Where an order stands, normalized across sources. Filter controls are built from
+/// [OrderStatus::ALL], so a new variant shows up in the UI once it is listed there.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OrderStatus {
+    #[default]
+    Unfulfilled,
+    PartiallyFulfilled,
+    Fulfilled,
+    Cancelled,
+    /// Fully refunded (Shopify `financial_status: refunded`, Etsy "Fully Refunded").
+    Refunded,
+    /// A source status this app doesn't recognize yet.
+    Other,
+}
+
+impl OrderStatus {
+    pub const ALL: [OrderStatus; 6] = [
+        OrderStatus::Unfulfilled,
+        OrderStatus::PartiallyFulfilled,
+        OrderStatus::Fulfilled,
+        OrderStatus::Cancelled,
+        OrderStatus::Refunded,
+        OrderStatus::Other,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OrderStatus::Unfulfilled => "Unfulfilled",
+            OrderStatus::PartiallyFulfilled => "Partially Fulfilled",
+            OrderStatus::Fulfilled => "Fulfilled",
+            OrderStatus::Cancelled => "Cancelled",
+            OrderStatus::Refunded => "Refunded",
+            OrderStatus::Other => "Other",
+        }
+    }
+
+    /// Cancelled and refunded orders need no work; they're hidden from the default views.
+    pub fn is_active(&self) -> bool {
+        !matches!(self, OrderStatus::Cancelled | OrderStatus::Refunded)
+    }
+
+    /// Inverse of [OrderStatus::label], for select values.
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.label() == label)
+    }
+}
+
+/// Health of the stored Etsy OAuth refresh token, from the last refresh attempt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EtsyTokenStatus {
+    /// No refresh token saved (Etsy not set up, or using a static `ETSY_SECRET`).
+    NotConnected,
+    Connected,
+    /// The refresh token chain runs out within a few days unless it is used or replaced.
+    ExpiringSoon { days_left: i64 },
+    /// Etsy rejected the last refresh, or the token has aged out; paste a new one.
+    NeedsReconnect { reason: String },
+}
+
+/// Which sources `fetch_all_orders` queries. A disabled source is skipped
+/// entirely, so it never contributes an error.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceToggles {
+    #[serde(default = "default_true")]
+    pub fetch_shopify: bool,
+    #[serde(default = "default_true")]
+    pub fetch_etsy: bool,
+}
+
+impl Default for SourceToggles {
+    fn default() -> Self {
+        Self {
+            fetch_shopify: true,
+            fetch_etsy: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Choices offered for [Settings::auto_refresh_minutes] (0 = off).
+pub const AUTO_REFRESH_OPTIONS: [u32; 5] = [0, 5, 15, 30, 60];
+
+/// Everything the Settings page edits, saved server-side as one document. Fields left `None`
+/// follow the env vars (`DISPLAY_TZ`, `AUTO_REFRESH_MINUTES`, `URGENT_WITHIN_DAYS`,
+/// `WARNING_WITHIN_DAYS`, `BASE_CURRENCY`) until changed in the UI.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub ui: UiPrefs,
+    #[serde(default)]
+    pub sources: SourceToggles,
+    /// Minutes between automatic order refreshes; 0 turns auto-refresh off. `None` uses
+    /// `AUTO_REFRESH_MINUTES` (or off).
+    #[serde(default)]
+    pub auto_refresh_minutes: Option<u32>,
+    /// Days left at or under which an order is urgent. `None` uses [URGENT_WITHIN_DAYS].
+    #[serde(default)]
+    pub urgent_within_days: Option<i64>,
+    /// Days left at or under which an order gets the warning highlight. `None` uses
+    /// [WARNING_WITHIN_DAYS].
+    #[serde(default)]
+    pub warning_within_days: Option<i64>,
+    /// Currency of catalog costs (ISO 4217). `None` uses [COST_CURRENCY].
+    #[serde(default)]
+    pub base_currency: Option<String>,
+}
+
+impl Settings {
+    pub fn due_thresholds(&self) -> DueThresholds {
+        DueThresholds {
+            urgent_within_days: self.urgent_within_days.unwrap_or(URGENT_WITHIN_DAYS),
+            warning_within_days: self.warning_within_days.unwrap_or(WARNING_WITHIN_DAYS),
+        }
+    }
+
+    pub fn cost_currency(&self) -> &str {
+        self.base_currency.as_deref().unwrap_or(COST_CURRENCY)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// UI preferences (persisted server-side, restored on startup)
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum ViewFilter {
+    #[default]
+    All,
+    Shopify,
+    Etsy,
+    Urgent,
+    /// Only orders the user has manually archived (hidden from every other view).
+    Archived,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum SortBy {
+    #[default]
+    DueDate,
+    OrderDate,
+    Customer,
+}
+
+/// Unit for displaying metal weights.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum WeightUnit {
+    #[default]
+    Grams,
+    TroyOunces,
+}
+
+impl WeightUnit {
+    pub const GRAMS_PER_TROY_OUNCE: f64 = 31.103_476_8;
+
+    /// Format a weight given in grams, e.g. "12.5 g" or "0.402 ozt".
+    pub fn format(&self, grams: f64) -> String {
+        match self {
+            WeightUnit::Grams => format!("{:.1} g", grams),
+            WeightUnit::TroyOunces => format!("{:.3} ozt", grams / Self::GRAMS_PER_TROY_OUNCE),
+        }
+    }
+}
+
+/// Zones offered in the settings dropdown. Any valid IANA name is accepted when saved.
+pub const COMMON_TIMEZONES: [&str; 12] = [
+    "UTC",
+    "America/New_York",
+    "America/Chicago",
+    "America/Denver",
+    "America/Phoenix",
+    "America/Los_Angeles",
+    "America/Anchorage",
+    "Pacific/Honolulu",
+    "Europe/London",
+    "Europe/Berlin",
+    "Asia/Tokyo",
+    "Australia/Sydney",
+];
+
+/// Parse an IANA zone name ("America/Chicago") for displaying dates.
+pub fn parse_display_tz(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("Unknown timezone: {}", name.trim()))
+}
+
+/// Rows-per-page choices offered in the table footer.
+pub const PAGE_SIZE_OPTIONS: [usize; 3] = [20, 50, 100];
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+fn default_page_size() -> usize {
+    DEFAULT_PAGE_SIZE
+}
+
+/// View preferences that survive a reload. The search query is intentionally
+/// not part of this: it is a throwaway, per-session filter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiPrefs {
+    #[serde(default)]
+    pub view_filter: ViewFilter,
+    #[serde(default)]
+    pub sort_by: SortBy,
+    #[serde(default)]
+    pub weight_unit: WeightUnit,
+    /// Show orders with no line items (hidden by default: they're rarely actionable).
+    #[serde(default)]
+    pub show_empty_orders: bool,
+    /// Show only orders with this status; `None` shows every active status, plus
+    /// cancelled/refunded ones when `show_cancelled` is set.
+    #[serde(default)]
+    pub status_filter: Option<OrderStatus>,
+    #[serde(default)]
+    pub show_cancelled: bool,
+    /// Orders table rows per page (one of [PAGE_SIZE_OPTIONS]).
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+    /// IANA zone for dates and day counts. `None` uses the server's `DISPLAY_TZ` (or UTC);
+    /// when saving, `None` keeps whatever zone is stored.
+    #[serde(default)]
+    pub display_tz: Option<String>,
+    /// Count shipped items in the weight/cost totals (off: totals are what's left to make).
+    #[serde(default)]
+    pub totals_include_shipped: bool,
+    /// Metal per casting batch, for [suggest_casting_batches].
+    #[serde(default = "default_crucible_capacity")]
+    pub crucible_capacity_g: f64,
+}
+
+fn default_crucible_capacity() -> f64 {
+    DEFAULT_CRUCIBLE_CAPACITY_G
+}
+
+impl Default for UiPrefs {
+    fn default() -> Self {
+        Self {
+            view_filter: ViewFilter::default(),
+            sort_by: SortBy::default(),
+            weight_unit: WeightUnit::default(),
+            show_empty_orders: false,
+            status_filter: None,
+            show_cancelled: false,
+            page_size: DEFAULT_PAGE_SIZE,
+            display_tz: None,
+            totals_include_shipped: false,
+            crucible_capacity_g: DEFAULT_CRUCIBLE_CAPACITY_G,
+        }
+    }
+}
+
+/// Default: orders due within this many days count as urgent (overdue ones included).
+pub const URGENT_WITHIN_DAYS: i64 = 3;
+/// Default: orders due within this many days get the warning highlight.
+pub const WARNING_WITHIN_DAYS: i64 = 7;
+
+/// Days-left cutoffs for urgency, from [Settings::due_thresholds].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DueThresholds {
+    /// Urgent view, urgent counts and the critical highlight.
+    pub urgent_within_days: i64,
+    /// Warning highlight.
+    pub warning_within_days: i64,
+}
+
+impl Default for DueThresholds {
+    fn default() -> Self {
+        Self {
+            urgent_within_days: URGENT_WITHIN_DAYS,
+            warning_within_days: WARNING_WITHIN_DAYS,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Order {
     pub id: String,
@@ -65,23 +396,333 @@ pub struct Order {
     pub due_date: DateTime<Utc>,
     pub total_price: f64,
     pub currency: String,
-    pub status: String,
-    pub shipping_address: Option<String>,
+    pub status: OrderStatus,
+    /// Payment state as reported by the source, lowercase snake_case (Shopify's
+    /// `financial_status`: "paid", "partially_refunded", ...). `None` when unknown.
+    #[serde(default)]
+    pub financial_status: Option<String>,
+    pub shipping_address: Option<ShippingAddress>,
+    /// The source-computed due date when `due_date` has been manually overridden.
+    #[serde(default)]
+    pub computed_due_date: Option<DateTime<Utc>>,
+    /// Fulfillment location for the whole order (Shopify); `None` when unknown or when
+    /// items ship from different places (see [Order::spans_multiple_locations]).
+    #[serde(default)]
+    pub location: Option<String>,
+    /// The source's original order date when it was in the future and `order_date`
+    /// was clamped to the fetch time (see [clamp_future_order_date]).
+    #[serde(default)]
+    pub raw_order_date: Option<DateTime<Utc>>,
+    /// Shopify test order (placed with a test gateway). Kept out of the queue, stats and
+    /// urgent/overdue counts unless test orders are explicitly shown.
+    #[serde(default)]
+    pub is_test: bool,
+    /// The order's page in the source's admin (Shopify admin, Etsy sold orders). `None`
+    /// when the source has no such page or the shop can't be determined.
+    #[serde(default)]
+    pub admin_url: Option<String>,
+}
+
+/// Postal address as sent by the source; parts the source left out are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShippingAddress {
+    /// Recipient, when the source provides one.
+    pub name: Option<String>,
+    pub line1: Option<String>,
+    pub line2: Option<String>,
+    pub city: Option<String>,
+    /// State / province.
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+    /// Country name, e.g. "Canada".
+    pub country: Option<String>,
+    /// ISO 3166-1 alpha-2 code, e.g. "CA", when the source sends one.
+    #[serde(default)]
+    pub country_code: Option<String>,
+}
+
+impl ShippingAddress {
+    /// One line for the detail view and search, e.g. "12 Main St, Springfield, IL 62701, US".
+    pub fn single_line(&self) -> String {
+        [
+            self.line1.clone(),
+            self.line2.clone(),
+            self.city.clone(),
+            join_present(&[&self.region, &self.postal_code], " "),
+            self.country.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
+    /// Whether `query` (already lowercased) appears in the address or is its country code.
+    pub fn matches_search(&self, query: &str) -> bool {
+        self.single_line().to_lowercase().contains(query)
+            || self.country_code.as_deref().is_some_and(|code| code.eq_ignore_ascii_case(query))
+    }
+
+    /// Multi-line layout for pasting into a shipping label tool.
+    pub fn label_text(&self) -> String {
+        let region_zip = join_present(&[&self.region, &self.postal_code], " ");
+        let city_line = join_present(&[&self.city, &region_zip], ", ");
+        [
+            self.name.clone(),
+            self.line1.clone(),
+            self.line2.clone(),
+            city_line,
+            self.country.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
+
+/// English name for an ISO 3166-1 alpha-2 country code (any case).
+pub fn country_name(code: &str) -> Option<&'static str> {
+    let code = code.trim().to_ascii_uppercase();
+    COUNTRY_NAMES
+        .binary_search_by(|(c, _)| (*c).cmp(code.as_str()))
+        .ok()
+        .map(|i| COUNTRY_NAMES[i].1)
+}
+
+/// ISO 3166-1 alpha-2 codes and names, sorted by code.
+const COUNTRY_NAMES: &[(&str, &str)] = &[
+    ("AD", "Andorra"), ("AE", "United Arab Emirates"), ("AF", "Afghanistan"),
+    ("AG", "Antigua and Barbuda"), ("AI", "Anguilla"), ("AL", "Albania"), ("AM", "Armenia"),
+    ("AO", "Angola"), ("AQ", "Antarctica"), ("AR", "Argentina"), ("AS", "American Samoa"),
+    ("AT", "Austria"), ("AU", "Australia"), ("AW", "Aruba"), ("AX", "Åland Islands"),
+    ("AZ", "Azerbaijan"), ("BA", "Bosnia and Herzegovina"), ("BB", "Barbados"),
+    ("BD", "Bangladesh"), ("BE", "Belgium"), ("BF", "Burkina Faso"), ("BG", "Bulgaria"),
+    ("BH", "Bahrain"), ("BI", "Burundi"), ("BJ", "Benin"), ("BL", "Saint Barthélemy"),
+    ("BM", "Bermuda"), ("BN", "Brunei"), ("BO", "Bolivia"), ("BQ", "Caribbean Netherlands"),
+    ("BR", "Brazil"), ("BS", "Bahamas"), ("BT", "Bhutan"), ("BV", "Bouvet Island"),
+    ("BW", "Botswana"), ("BY", "Belarus"), ("BZ", "Belize"), ("CA", "Canada"),
+    ("CC", "Cocos (Keeling) Islands"), ("CD", "Democratic Republic of the Congo"),
+    ("CF", "Central African Republic"), ("CG", "Republic of the Congo"), ("CH", "Switzerland"),
+    ("CI", "Côte d'Ivoire"), ("CK", "Cook Islands"), ("CL", "Chile"), ("CM", "Cameroon"),
+    ("CN", "China"), ("CO", "Colombia"), ("CR", "Costa Rica"), ("CU", "Cuba"), ("CV", "Cape Verde"),
+    ("CW", "Curaçao"), ("CX", "Christmas Island"), ("CY", "Cyprus"), ("CZ", "Czechia"),
+    ("DE", "Germany"), ("DJ", "Djibouti"), ("DK", "Denmark"), ("DM", "Dominica"),
+    ("DO", "Dominican Republic"), ("DZ", "Algeria"), ("EC", "Ecuador"), ("EE", "Estonia"),
+    ("EG", "Egypt"), ("EH", "Western Sahara"), ("ER", "Eritrea"), ("ES", "Spain"),
+    ("ET", "Ethiopia"), ("FI", "Finland"), ("FJ", "Fiji"), ("FK", "Falkland Islands"),
+    ("FM", "Micronesia"), ("FO", "Faroe Islands"), ("FR", "France"), ("GA", "Gabon"),
+    ("GB", "United Kingdom"), ("GD", "Grenada"), ("GE", "Georgia"), ("GF", "French Guiana"),
+    ("GG", "Guernsey"), ("GH", "Ghana"), ("GI", "Gibraltar"), ("GL", "Greenland"), ("GM", "Gambia"),
+    ("GN", "Guinea"), ("GP", "Guadeloupe"), ("GQ", "Equatorial Guinea"), ("GR", "Greece"),
+    ("GS", "South Georgia and the South Sandwich Islands"), ("GT", "Guatemala"), ("GU", "Guam"),
+    ("GW", "Guinea-Bissau"), ("GY", "Guyana"), ("HK", "Hong Kong"),
+    ("HM", "Heard Island and McDonald Islands"), ("HN", "Honduras"), ("HR", "Croatia"),
+    ("HT", "Haiti"), ("HU", "Hungary"), ("ID", "Indonesia"), ("IE", "Ireland"), ("IL", "Israel"),
+    ("IM", "Isle of Man"), ("IN", "India"), ("IO", "British Indian Ocean Territory"),
+    ("IQ", "Iraq"), ("IR", "Iran"), ("IS", "Iceland"), ("IT", "Italy"), ("JE", "Jersey"),
+    ("JM", "Jamaica"), ("JO", "Jordan"), ("JP", "Japan"), ("KE", "Kenya"), ("KG", "Kyrgyzstan"),
+    ("KH", "Cambodia"), ("KI", "Kiribati"), ("KM", "Comoros"), ("KN", "Saint Kitts and Nevis"),
+    ("KP", "North Korea"), ("KR", "South Korea"), ("KW", "Kuwait"), ("KY", "Cayman Islands"),
+    ("KZ", "Kazakhstan"), ("LA", "Laos"), ("LB", "Lebanon"), ("LC", "Saint Lucia"),
+    ("LI", "Liechtenstein"), ("LK", "Sri Lanka"), ("LR", "Liberia"), ("LS", "Lesotho"),
+    ("LT", "Lithuania"), ("LU", "Luxembourg"), ("LV", "Latvia"), ("LY", "Libya"), ("MA", "Morocco"),
+    ("MC", "Monaco"), ("MD", "Moldova"), ("ME", "Montenegro"), ("MF", "Saint Martin"),
+    ("MG", "Madagascar"), ("MH", "Marshall Islands"), ("MK", "North Macedonia"), ("ML", "Mali"),
+    ("MM", "Myanmar"), ("MN", "Mongolia"), ("MO", "Macau"), ("MP", "Northern Mariana Islands"),
+    ("MQ", "Martinique"), ("MR", "Mauritania"), ("MS", "Montserrat"), ("MT", "Malta"),
+    ("MU", "Mauritius"), ("MV", "Maldives"), ("MW", "Malawi"), ("MX", "Mexico"), ("MY", "Malaysia"),
+    ("MZ", "Mozambique"), ("NA", "Namibia"), ("NC", "New Caledonia"), ("NE", "Niger"),
+    ("NF", "Norfolk Island"), ("NG", "Nigeria"), ("NI", "Nicaragua"), ("NL", "Netherlands"),
+    ("NO", "Norway"), ("NP", "Nepal"), ("NR", "Nauru"), ("NU", "Niue"), ("NZ", "New Zealand"),
+    ("OM", "Oman"), ("PA", "Panama"), ("PE", "Peru"), ("PF", "French Polynesia"),
+    ("PG", "Papua New Guinea"), ("PH", "Philippines"), ("PK", "Pakistan"), ("PL", "Poland"),
+    ("PM", "Saint Pierre and Miquelon"), ("PN", "Pitcairn Islands"), ("PR", "Puerto Rico"),
+    ("PS", "Palestine"), ("PT", "Portugal"), ("PW", "Palau"), ("PY", "Paraguay"), ("QA", "Qatar"),
+    ("RE", "Réunion"), ("RO", "Romania"), ("RS", "Serbia"), ("RU", "Russia"), ("RW", "Rwanda"),
+    ("SA", "Saudi Arabia"), ("SB", "Solomon Islands"), ("SC", "Seychelles"), ("SD", "Sudan"),
+    ("SE", "Sweden"), ("SG", "Singapore"), ("SH", "Saint Helena"), ("SI", "Slovenia"),
+    ("SJ", "Svalbard and Jan Mayen"), ("SK", "Slovakia"), ("SL", "Sierra Leone"),
+    ("SM", "San Marino"), ("SN", "Senegal"), ("SO", "Somalia"), ("SR", "Suriname"),
+    ("SS", "South Sudan"), ("ST", "São Tomé and Príncipe"), ("SV", "El Salvador"),
+    ("SX", "Sint Maarten"), ("SY", "Syria"), ("SZ", "Eswatini"), ("TC", "Turks and Caicos Islands"),
+    ("TD", "Chad"), ("TF", "French Southern Territories"), ("TG", "Togo"), ("TH", "Thailand"),
+    ("TJ", "Tajikistan"), ("TK", "Tokelau"), ("TL", "Timor-Leste"), ("TM", "Turkmenistan"),
+    ("TN", "Tunisia"), ("TO", "Tonga"), ("TR", "Turkey"), ("TT", "Trinidad and Tobago"),
+    ("TV", "Tuvalu"), ("TW", "Taiwan"), ("TZ", "Tanzania"), ("UA", "Ukraine"), ("UG", "Uganda"),
+    ("UM", "U.S. Outlying Islands"), ("US", "United States"), ("UY", "Uruguay"),
+    ("UZ", "Uzbekistan"), ("VA", "Vatican City"), ("VC", "Saint Vincent and the Grenadines"),
+    ("VE", "Venezuela"), ("VG", "British Virgin Islands"), ("VI", "U.S. Virgin Islands"),
+    ("VN", "Vietnam"), ("VU", "Vanuatu"), ("WF", "Wallis and Futuna"), ("WS", "Samoa"),
+    ("YE", "Yemen"), ("YT", "Mayotte"), ("ZA", "South Africa"), ("ZM", "Zambia"),
+    ("ZW", "Zimbabwe"),
+];
+
+/// Join the non-blank parts with `sep`; `None` when all are blank.
+fn join_present(parts: &[&Option<String>], sep: &str) -> Option<String> {
+    let present: Vec<&str> = parts
+        .iter()
+        .filter_map(|p| p.as_deref())
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    (!present.is_empty()).then(|| present.join(sep))
+}
+
+/// How far ahead of our clock an order date may be before it's treated as bad data.
+pub const FUTURE_ORDER_DATE_TOLERANCE_MINUTES: i64 = 15;
+
+/// Clamp an order date that lies in the future (beyond the tolerance) to `now`, so due
+/// dates and urgency stay sane. Returns the date to use and, when clamped, the raw one.
+pub fn clamp_future_order_date(
+    order_date: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> (DateTime<Utc>, Option<DateTime<Utc>>) {
+    if order_date > now + chrono::Duration::minutes(FUTURE_ORDER_DATE_TOLERANCE_MINUTES) {
+        (now, Some(order_date))
+    } else {
+        (order_date, None)
+    }
 }
 
 impl Order {
-    pub fn days_until_due(&self) -> i64 {
-        let now = Utc::now();
-        (self.due_date - now).num_days()
+    /// Shopify returns fully refunded / tip-only orders with no line items; they carry
+    /// nothing to make and are hidden by default.
+    pub fn has_items(&self) -> bool {
+        !self.items.is_empty()
+    }
+
+    /// Total pieces across line items (quantities summed).
+    pub fn item_count(&self) -> u32 {
+        self.items.iter().map(|i| i.quantity).sum()
+    }
+
+    /// Distinct item locations, in item order.
+    pub fn item_locations(&self) -> Vec<&str> {
+        let mut locations: Vec<&str> = Vec::new();
+        for loc in self.items.iter().filter_map(|i| i.location.as_deref()) {
+            if !locations.contains(&loc) {
+                locations.push(loc);
+            }
+        }
+        locations
+    }
+
+    /// Items ship from more than one location, so the order needs splitting.
+    pub fn spans_multiple_locations(&self) -> bool {
+        self.item_locations().len() > 1
+    }
+
+    /// Cancelled or fully refunded (see [OrderStatus::is_active]).
+    pub fn is_inactive(&self) -> bool {
+        !self.status.is_active()
+    }
+
+    /// Part of the active queue that stats and urgent/overdue counts cover: has items, is
+    /// not cancelled/refunded, not a test order and not archived (`archived_ids`).
+    pub fn counts_in_stats(&self, archived_ids: &[String]) -> bool {
+        self.has_items() && !self.is_inactive() && !self.is_test && !archived_ids.contains(&self.id)
+    }
+
+    /// Whether `item` (one of this order's items) has already shipped: the whole order is
+    /// fulfilled, or the item itself is. Unknown item status counts as not shipped.
+    pub fn item_shipped(&self, item: &OrderItem) -> bool {
+        self.status == OrderStatus::Fulfilled || item.fulfilled == Some(true)
+    }
+
+    /// Status for display, combining fulfillment with payment where that adds something,
+    /// e.g. "Paid / Unfulfilled" or "Refunded". Unrecognized payment states fall back to
+    /// the fulfillment label alone.
+    pub fn status_label(&self) -> String {
+        let fulfillment = self.status.label();
+        match (self.status, self.financial_status.as_deref()) {
+            (OrderStatus::Cancelled | OrderStatus::Refunded, _) => fulfillment.to_string(),
+            (_, Some("refunded")) => "Refunded".to_string(),
+            (_, Some("voided")) => "Voided".to_string(),
+            (_, Some("partially_refunded")) => format!("Partially Refunded / {}", fulfillment),
+            (OrderStatus::Unfulfilled, Some("paid")) => "Paid / Unfulfilled".to_string(),
+            (OrderStatus::Unfulfilled, Some("pending" | "authorized" | "partially_paid")) => {
+                "Awaiting Payment".to_string()
+            }
+            _ => fulfillment.to_string(),
+        }
+    }
+
+    pub fn status_badge_class(&self) -> &'static str {
+        match (self.status, self.financial_status.as_deref()) {
+            (OrderStatus::Cancelled | OrderStatus::Refunded, _) | (_, Some("refunded" | "voided")) => {
+                "badge-blocked"
+            }
+            (OrderStatus::Fulfilled, _) => "badge-success",
+            (OrderStatus::PartiallyFulfilled, _) | (_, Some("partially_refunded")) => "badge-method",
+            _ => "badge-nebula",
+        }
+    }
+
+    /// Replace the due date with a manual override, remembering the computed one.
+    pub fn apply_due_date_override(&mut self, due: DateTime<Utc>) {
+        if self.computed_due_date.is_none() {
+            self.computed_due_date = Some(self.due_date);
+        }
+        self.due_date = due;
+    }
+
+    /// Drop a manual override and restore the computed due date.
+    pub fn clear_due_date_override(&mut self) {
+        if let Some(computed) = self.computed_due_date.take() {
+            self.due_date = computed;
+        }
+    }
+
+    pub fn has_due_date_override(&self) -> bool {
+        self.computed_due_date.is_some()
+    }
+
+    /// Calendar days from today to the due date, both taken in `tz` (0 = due today).
+    pub fn days_until_due(&self, tz: Tz) -> i64 {
+        let today = Utc::now().with_timezone(&tz).date_naive();
+        let due = self.due_date.with_timezone(&tz).date_naive();
+        (due - today).num_days()
+    }
+
+    /// Due within the urgent threshold, or already overdue.
+    pub fn is_urgent(&self, tz: Tz, thresholds: DueThresholds) -> bool {
+        self.days_until_due(tz) <= thresholds.urgent_within_days
+    }
+
+    pub fn is_overdue(&self, tz: Tz) -> bool {
+        self.days_until_due(tz) < 0
     }
 
-    pub fn urgency_class(&self) -> &'static str {
-        let days = self.days_until_due();
+    /// Whole days since the order was placed, in `tz`.
+    pub fn age_days(&self, tz: Tz) -> i64 {
+        let today = Utc::now().with_timezone(&tz).date_naive();
+        let placed = self.order_date.with_timezone(&tz).date_naive();
+        (today - placed).num_days()
+    }
+
+    /// Share of the lead time (order date to due date) already used, as a percentage.
+    /// Goes past 100 once overdue. A due date on or before the order date has no budget
+    /// to spend, so it counts as 100% until it is overdue.
+    pub fn lead_time_consumed_pct(&self, tz: Tz) -> f64 {
+        let placed = self.order_date.with_timezone(&tz).date_naive();
+        let due = self.due_date.with_timezone(&tz).date_naive();
+        let budget = (due - placed).num_days();
+        if budget <= 0 {
+            return if self.is_overdue(tz) { 200.0 } else { 100.0 };
+        }
+        (self.age_days(tz).max(0) as f64 / budget as f64) * 100.0
+    }
+
+    pub fn urgency_class(&self, tz: Tz, thresholds: DueThresholds) -> &'static str {
+        let days = self.days_until_due(tz);
         if days < 0 {
             "urgency-overdue"
-        } else if days <= 3 {
+        } else if days <= thresholds.urgent_within_days {
             "urgency-critical"
-        } else if days <= 7 {
+        } else if days <= thresholds.warning_within_days {
             "urgency-warning"
         } else {
             "urgency-ok"
@@ -99,6 +740,49 @@ pub struct OrderItem {
     pub variant_info: Option<String>,
     /// Product thumbnail URL (from Etsy listing image or Shopify line item image).
     pub image_url: Option<String>,
+    /// Every metal in a multi-metal (two-tone) piece, primary first. Empty or a single entry
+    /// for ordinary pieces; `metal_type` stays the primary for older callers.
+    #[serde(default)]
+    pub metals: Vec<MetalType>,
+    /// Where this item ships from (Shopify location or fulfillment service), when known.
+    #[serde(default)]
+    pub location: Option<String>,
+    /// Whether this line item has shipped, from Shopify's line-item fulfillment status.
+    /// `None` when the source doesn't report it per item.
+    #[serde(default)]
+    pub fulfilled: Option<bool>,
+}
+
+impl OrderItem {
+    /// Metals used for costing and display: `metals` when set, else just `metal_type`.
+    pub fn all_metals(&self) -> Vec<MetalType> {
+        if self.metals.is_empty() {
+            vec![self.metal_type.clone()]
+        } else {
+            self.metals.clone()
+        }
+    }
+
+    pub fn is_multi_metal(&self) -> bool {
+        self.metals.len() > 1
+    }
+
+    /// Badge label, e.g. "Silver" or "Silver + Gold Plated" for two-tone pieces.
+    pub fn metal_label(&self) -> String {
+        self.all_metals()
+            .iter()
+            .map(|m| m.display_name())
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+
+    pub fn metal_badge_class(&self) -> &'static str {
+        if self.is_multi_metal() {
+            "badge-two-tone"
+        } else {
+            self.metal_type.display_class()
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -129,24 +813,84 @@ pub struct ItemCostWeight {
     pub weight_g: f64,
 }
 
+/// How an order item was matched to a piece_costs row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MatchStrategy {
+    /// One of the row's `product_keys` matched the item name.
+    ProductKey,
+    /// The row's `design_key` equals the normalized item name.
+    DesignKey,
+    /// Substring overlap between `design_key` and the item name. `score` is the
+    /// length ratio of the shorter to the longer string (1.0 = identical).
+    Fuzzy { score: f64 },
+}
+
+impl MatchStrategy {
+    /// Short label for a confidence badge.
+    pub fn label(&self) -> String {
+        match self {
+            MatchStrategy::ProductKey => "product key".to_string(),
+            MatchStrategy::DesignKey => "design key".to_string(),
+            MatchStrategy::Fuzzy { score } => format!("fuzzy {:.0}%", score * 100.0),
+        }
+    }
+
+    pub fn is_fuzzy(&self) -> bool {
+        matches!(self, MatchStrategy::Fuzzy { .. })
+    }
+}
+
+/// A resolved piece_costs match: which row, how it was found, and the resulting cost/weight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PieceCostMatch {
+    pub design_key: String,
+    pub strategy: MatchStrategy,
+    pub cost_weight: ItemCostWeight,
+}
+
 /// Match an order item to a piece_costs row and return cost/weight for the item's metal type.
 pub fn lookup_piece_cost(item: &OrderItem, piece_costs: &[PieceCostRow]) -> Option<ItemCostWeight> {
+    lookup_piece_cost_match(item, piece_costs).map(|m| m.cost_weight)
+}
+
+/// Like [lookup_piece_cost], but also reports which row matched and by what strategy,
+/// so a questionable match can be audited in the UI.
+pub fn lookup_piece_cost_match(item: &OrderItem, piece_costs: &[PieceCostRow]) -> Option<PieceCostMatch> {
     let item_name_normalized = item.name.to_lowercase().trim().to_string();
     let item_ring = item.ring_size.as_ref().map(|s| s.trim().to_string());
+    let resolve = |row: &PieceCostRow, strategy: MatchStrategy| {
+        pick_cost_weight_for_item(row, item).map(|cost_weight| PieceCostMatch {
+            design_key: row.design_key.clone(),
+            strategy,
+            cost_weight,
+        })
+    };
 
-    // 1) Try match by product_keys
+    // 1) Try match by product_keys: exact name or whole-word containment ("ring" must not
+    //    match "earring"). The longest matching key wins, so "gold ring" beats "ring".
+    let item_words = words(&item.name);
+    let mut best: Option<(usize, &PieceCostRow)> = None;
     for row in piece_costs {
-        if let Some(keys) = &row.product_keys {
-            if keys.iter().any(|k| {
-                k.trim().to_lowercase() == item_name_normalized
-                    || item.name.to_lowercase().contains(&k.trim().to_lowercase())
-            }) {
-                if ring_matches(&row.ring_size, &item_ring) {
-                    return pick_cost_weight(row, &item.metal_type);
-                }
+        let Some(keys) = &row.product_keys else {
+            continue;
+        };
+        if !ring_matches(&row.ring_size, &item_ring) {
+            continue;
+        }
+        for key in keys {
+            let key_normalized = key.trim().to_lowercase();
+            if key_normalized.is_empty() {
+                continue;
+            }
+            let hit = key_normalized == item_name_normalized || contains_words(&item_words, &words(&key_normalized));
+            if hit && best.is_none_or(|(len, _)| key_normalized.len() > len) {
+                best = Some((key_normalized.len(), row));
             }
         }
     }
+    if let Some((_, row)) = best {
+        return resolve(row, MatchStrategy::ProductKey);
+    }
 
     // 2) Try match by design_key (normalized item name or contains)
     for row in piece_costs {
@@ -156,7 +900,19 @@ pub fn lookup_piece_cost(item: &OrderItem, piece_costs: &[PieceCostRow]) -> Opti
             || design_lower.contains(&item_name_normalized)
         {
             if ring_matches(&row.ring_size, &item_ring) {
-                return pick_cost_weight(row, &item.metal_type);
+                let strategy = if design_lower == item_name_normalized {
+                    MatchStrategy::DesignKey
+                } else {
+                    let (short, long) = if design_lower.len() < item_name_normalized.len() {
+                        (design_lower.len(), item_name_normalized.len())
+                    } else {
+                        (item_name_normalized.len(), design_lower.len())
+                    };
+                    MatchStrategy::Fuzzy {
+                        score: short as f64 / long.max(1) as f64,
+                    }
+                };
+                return resolve(row, strategy);
             }
         }
     }
@@ -164,6 +920,20 @@ pub fn lookup_piece_cost(item: &OrderItem, piece_costs: &[PieceCostRow]) -> Opti
     None
 }
 
+/// Lowercased alphanumeric words of `s`.
+fn words(s: &str) -> Vec<String> {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// True when `needle` appears in `haystack` as a contiguous run of whole words.
+fn contains_words(haystack: &[String], needle: &[String]) -> bool {
+    !needle.is_empty() && haystack.windows(needle.len()).any(|w| w == needle)
+}
+
 fn ring_matches(row_ring: &Option<String>, item_ring: &Option<String>) -> bool {
     match (row_ring, item_ring) {
         (None, _) => true,
@@ -173,6 +943,25 @@ fn ring_matches(row_ring: &Option<String>, item_ring: &Option<String>) -> bool {
     }
 }
 
+/// Cost/weight for all of an item's metals: a two-tone piece sums each metal's columns.
+fn pick_cost_weight_for_item(row: &PieceCostRow, item: &OrderItem) -> Option<ItemCostWeight> {
+    if !item.is_multi_metal() {
+        return pick_cost_weight(row, &item.metal_type);
+    }
+    let parts: Vec<ItemCostWeight> = item
+        .metals
+        .iter()
+        .filter_map(|m| pick_cost_weight(row, m))
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+    Some(ItemCostWeight {
+        cost_usd: parts.iter().map(|p| p.cost_usd).sum(),
+        weight_g: parts.iter().map(|p| p.weight_g).sum(),
+    })
+}
+
 fn pick_cost_weight(row: &PieceCostRow, metal: &MetalType) -> Option<ItemCostWeight> {
     let (cost, weight) = match metal {
         MetalType::Silver => (
@@ -203,3 +992,416 @@ fn pick_cost_weight(row: &PieceCostRow, metal: &MetalType) -> Option<ItemCostWei
         None
     }
 }
+
+// ---------------------------------------------------------------------------
+// Unmatched products report (which piece_costs rows are missing)
+// ---------------------------------------------------------------------------
+
+/// A distinct item that [lookup_piece_cost] can't price, with how often it occurs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnmatchedProduct {
+    pub name: String,
+    pub variant_info: Option<String>,
+    pub metal: String,
+    pub ring_size: Option<String>,
+    /// Number of line items across the given orders.
+    pub count: usize,
+}
+
+/// Distinct `(name, variant, metal, ring size)` items with no piece_costs match, most
+/// frequent first.
+pub fn unmatched_products(orders: &[Order], piece_costs: &[PieceCostRow]) -> Vec<UnmatchedProduct> {
+    let mut report: Vec<UnmatchedProduct> = Vec::new();
+    for item in orders.iter().flat_map(|o| o.items.iter()) {
+        if lookup_piece_cost(item, piece_costs).is_some() {
+            continue;
+        }
+        let metal = item.metal_label();
+        let existing = report.iter_mut().find(|p| {
+            p.name == item.name
+                && p.variant_info == item.variant_info
+                && p.metal == metal
+                && p.ring_size == item.ring_size
+        });
+        match existing {
+            Some(p) => p.count += 1,
+            None => report.push(UnmatchedProduct {
+                name: item.name.clone(),
+                variant_info: item.variant_info.clone(),
+                metal,
+                ring_size: item.ring_size.clone(),
+                count: 1,
+            }),
+        }
+    }
+    report.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    report
+}
+
+// ---------------------------------------------------------------------------
+// Casting batch suggestions (group due-soon pieces of one metal per crucible load)
+// ---------------------------------------------------------------------------
+
+/// Crucible load used when none is configured.
+pub const DEFAULT_CRUCIBLE_CAPACITY_G: f64 = 250.0;
+
+/// Pieces of one order line placed in a batch (or lacking weight data).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchPiece {
+    pub order_id: String,
+    pub order_number: String,
+    pub item_name: String,
+    pub ring_size: Option<String>,
+    pub quantity: u32,
+    /// Catalog weight of all `quantity` pieces; 0 in the needs-data bucket.
+    pub weight_g: f64,
+    pub due_date: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CastingBatch {
+    pub pieces: Vec<BatchPiece>,
+    pub total_weight_g: f64,
+}
+
+/// Suggested batches for one metal; see [suggest_casting_batches].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CastingPlan {
+    pub batches: Vec<CastingBatch>,
+    /// Pieces with no piece_costs weight, which can't be planned by weight.
+    pub needs_data: Vec<BatchPiece>,
+}
+
+/// Group unshipped `metal` pieces from live orders due within `within_days` into batches
+/// of at most `capacity_g`. Pieces go in due-date order into the first batch with room,
+/// so the earliest work lands in the first batches; a piece heavier than the crucible
+/// gets a batch of its own. Two-tone pieces are planned under their primary metal.
+pub fn suggest_casting_batches(
+    orders: &[Order],
+    piece_costs: &[PieceCostRow],
+    metal: &MetalType,
+    within_days: i64,
+    capacity_g: f64,
+    tz: Tz,
+) -> CastingPlan {
+    let mut lines: Vec<(&Order, &OrderItem)> = orders
+        .iter()
+        .filter(|o| !o.is_inactive() && o.days_until_due(tz) <= within_days)
+        .flat_map(|o| {
+            o.items
+                .iter()
+                .filter(move |i| &i.metal_type == metal && !o.item_shipped(i))
+                .map(move |i| (o, i))
+        })
+        .collect();
+    lines.sort_by(|a, b| a.0.due_date.cmp(&b.0.due_date));
+
+    let piece = |order: &Order, item: &OrderItem, quantity: u32, weight_g: f64| BatchPiece {
+        order_id: order.id.clone(),
+        order_number: order.order_number.clone(),
+        item_name: item.name.clone(),
+        ring_size: item.ring_size.clone(),
+        quantity,
+        weight_g,
+        due_date: order.due_date,
+    };
+    let mut plan = CastingPlan::default();
+    for (order, item) in lines {
+        let unit_weight = lookup_piece_cost(item, piece_costs)
+            .map(|cw| cw.weight_g)
+            .filter(|w| *w > 0.0);
+        let Some(unit_weight) = unit_weight else {
+            plan.needs_data.push(piece(order, item, item.quantity, 0.0));
+            continue;
+        };
+        for _ in 0..item.quantity {
+            let idx = match plan
+                .batches
+                .iter()
+                .position(|b| b.total_weight_g + unit_weight <= capacity_g)
+            {
+                Some(idx) => idx,
+                None => {
+                    plan.batches.push(CastingBatch::default());
+                    plan.batches.len() - 1
+                }
+            };
+            let batch = &mut plan.batches[idx];
+            batch.total_weight_g += unit_weight;
+            // Another piece of the same line in this batch just bumps its quantity.
+            let same_line = batch
+                .pieces
+                .last_mut()
+                .filter(|p| p.order_id == order.id && p.item_name == item.name && p.ring_size == item.ring_size);
+            match same_line {
+                Some(p) => {
+                    p.quantity += 1;
+                    p.weight_g += unit_weight;
+                }
+                None => batch.pieces.push(piece(order, item, 1, unit_weight)),
+            }
+        }
+    }
+    plan
+}
+
+// ---------------------------------------------------------------------------
+// JSON export (self-describing snapshot of the orders list)
+// ---------------------------------------------------------------------------
+
+/// Filters that were applied when an export was taken.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportFilter {
+    pub view: ViewFilter,
+    pub status: Option<OrderStatus>,
+    pub show_cancelled: bool,
+    pub show_empty_orders: bool,
+    pub location: Option<String>,
+    pub currency: Option<String>,
+    pub search: String,
+}
+
+/// Orders plus enough metadata to tell later what the file contains.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrdersExport {
+    pub exported_at: DateTime<Utc>,
+    /// `None` when every loaded order was exported.
+    pub filter: Option<ExportFilter>,
+    pub order_count: usize,
+    /// Order count per source display name.
+    pub by_source: std::collections::BTreeMap<String, usize>,
+    pub orders: Vec<Order>,
+}
+
+impl OrdersExport {
+    pub fn new(orders: Vec<Order>, filter: Option<ExportFilter>) -> Self {
+        let mut by_source = std::collections::BTreeMap::new();
+        for order in &orders {
+            *by_source.entry(order.source.display_name().to_string()).or_insert(0) += 1;
+        }
+        Self {
+            exported_at: Utc::now(),
+            filter,
+            order_count: orders.len(),
+            by_source,
+            orders,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost_row(design_key: &str, product_keys: &[&str], usd: f64) -> PieceCostRow {
+        PieceCostRow {
+            design_key: design_key.to_string(),
+            ring_size: None,
+            volume_cm3: None,
+            silver_g: Some(5.0),
+            silver_usd: Some(usd),
+            gold_g: Some(5.0),
+            gold_usd: Some(usd),
+            bronze_g: None,
+            bronze_usd: None,
+            wax_usd: None,
+            product_keys: Some(product_keys.iter().map(|k| k.to_string()).collect()),
+        }
+    }
+
+    fn item(name: &str) -> OrderItem {
+        let metals = MetalType::detect_all(name);
+        OrderItem {
+            name: name.to_string(),
+            quantity: 1,
+            price: 0.0,
+            metal_type: metals.first().cloned().unwrap_or(MetalType::Unknown),
+            ring_size: None,
+            variant_info: None,
+            image_url: None,
+            metals,
+            location: None,
+            fulfilled: None,
+        }
+    }
+
+    /// An unfulfilled Shopify order with one item, placed `placed_days_ago` and due in `due_in_days`.
+    fn order(placed_days_ago: i64, due_in_days: i64) -> Order {
+        let now = Utc::now();
+        Order {
+            id: "1".to_string(),
+            source: OrderSource::Shopify,
+            order_number: "#1001".to_string(),
+            customer_name: "Ada Lovelace".to_string(),
+            items: vec![item("Sterling Silver Ring")],
+            order_date: now - chrono::Duration::days(placed_days_ago),
+            due_date: now + chrono::Duration::days(due_in_days),
+            total_price: 100.0,
+            currency: "USD".to_string(),
+            status: OrderStatus::Unfulfilled,
+            financial_status: None,
+            shipping_address: None,
+            computed_due_date: None,
+            location: None,
+            raw_order_date: None,
+            is_test: false,
+            admin_url: None,
+        }
+    }
+
+    #[test]
+    fn stats_skip_empty_inactive_test_and_archived_orders() {
+        let archived = vec!["9".to_string()];
+        assert!(order(1, 5).counts_in_stats(&archived));
+        let empty = Order { items: Vec::new(), ..order(1, 5) };
+        let cancelled = Order { status: OrderStatus::Cancelled, ..order(1, 5) };
+        let test = Order { is_test: true, ..order(1, 5) };
+        let archived_order = Order { id: "9".to_string(), ..order(1, 5) };
+        for o in [empty, cancelled, test, archived_order] {
+            assert!(!o.counts_in_stats(&archived), "{:?}", o);
+        }
+    }
+
+    #[test]
+    fn product_key_matches_whole_words_only() {
+        let rows = [cost_row("band-01", &["ring"], 10.0)];
+        assert_eq!(lookup_piece_cost_match(&item("Sterling Silver Earring"), &rows), None);
+
+        let hit = lookup_piece_cost_match(&item("Sterling Silver Ring"), &rows).unwrap();
+        assert_eq!(hit.design_key, "band-01");
+        assert_eq!(hit.strategy, MatchStrategy::ProductKey);
+    }
+
+    #[test]
+    fn longest_product_key_wins() {
+        let generic = cost_row("generic", &["ring"], 10.0);
+        let signet = cost_row("gold-signet", &["gold ring"], 50.0);
+        let gold_ring = item("14k Gold Ring");
+
+        for rows in [[generic.clone(), signet.clone()], [signet, generic]] {
+            let hit = lookup_piece_cost_match(&gold_ring, &rows).unwrap();
+            assert_eq!(hit.design_key, "gold-signet");
+            assert_eq!(hit.cost_weight.cost_usd, 50.0);
+        }
+    }
+
+    #[test]
+    fn money_uses_symbol_or_code() {
+        assert_eq!(format_money(12.5, "USD"), "$12.50");
+        assert_eq!(format_money(8.0, " eur "), "\u{20ac}8.00");
+        assert_eq!(format_money(40.0, "CHF"), "CHF 40.00");
+        assert_eq!(format_money(1500.4, "JPY"), "\u{a5}1500");
+        assert_eq!(currency_symbol("XYZ"), "XYZ");
+    }
+
+    #[test]
+    fn status_label_combines_payment_and_fulfillment() {
+        let with = |status: OrderStatus, financial: &str| Order {
+            status,
+            financial_status: Some(financial.to_string()),
+            ..order(1, 5)
+        };
+        assert_eq!(
+            with(OrderStatus::Unfulfilled, "paid").status_label(),
+            "Paid / Unfulfilled"
+        );
+        assert_eq!(
+            with(OrderStatus::Unfulfilled, "pending").status_label(),
+            "Awaiting Payment"
+        );
+        assert_eq!(
+            with(OrderStatus::Fulfilled, "partially_refunded").status_label(),
+            "Partially Refunded / Fulfilled"
+        );
+        assert_eq!(
+            with(OrderStatus::Fulfilled, "refunded").status_label(),
+            "Refunded"
+        );
+        assert_eq!(
+            with(OrderStatus::Cancelled, "refunded").status_label(),
+            "Cancelled"
+        );
+        assert_eq!(
+            with(OrderStatus::Fulfilled, "something_new").status_label(),
+            "Fulfilled"
+        );
+    }
+
+    #[test]
+    fn lead_time_consumed_pct_tracks_elapsed_share() {
+        assert_eq!(order(5, 5).lead_time_consumed_pct(Tz::UTC), 50.0);
+        assert_eq!(order(0, 10).lead_time_consumed_pct(Tz::UTC), 0.0);
+        // No lead time at all: fully used until overdue, then past it.
+        assert_eq!(order(0, 0).lead_time_consumed_pct(Tz::UTC), 100.0);
+        assert_eq!(order(0, -1).lead_time_consumed_pct(Tz::UTC), 200.0);
+    }
+
+    #[test]
+    fn future_order_dates_are_clamped_past_the_tolerance() {
+        let now = Utc::now();
+        let slightly_ahead =
+            now + chrono::Duration::minutes(FUTURE_ORDER_DATE_TOLERANCE_MINUTES - 1);
+        assert_eq!(
+            clamp_future_order_date(slightly_ahead, now),
+            (slightly_ahead, None)
+        );
+
+        let far_ahead = now + chrono::Duration::hours(2);
+        assert_eq!(
+            clamp_future_order_date(far_ahead, now),
+            (now, Some(far_ahead))
+        );
+    }
+
+    #[test]
+    fn casting_batches_fill_by_due_date_up_to_capacity() {
+        let rows = [cost_row("band-01", &["ring"], 10.0)];
+        let ring = |qty: u32| OrderItem {
+            quantity: qty,
+            ..item("Sterling Silver Ring")
+        };
+        let soon = Order {
+            id: "a".to_string(),
+            items: vec![ring(3)],
+            ..order(1, 1)
+        };
+        let later = Order {
+            id: "b".to_string(),
+            items: vec![ring(1)],
+            ..order(1, 2)
+        };
+        let too_late = Order {
+            id: "c".to_string(),
+            items: vec![ring(1)],
+            ..order(1, 30)
+        };
+        let unknown = Order {
+            id: "d".to_string(),
+            items: vec![item("Sterling Silver Cufflinks")],
+            ..order(1, 1)
+        };
+        let gold = Order {
+            id: "e".to_string(),
+            items: vec![item("14k Gold Ring")],
+            ..order(1, 1)
+        };
+        let orders = [later, too_late, unknown, gold, soon];
+
+        // 5 g per ring, 12 g crucible: two rings per batch.
+        let plan = suggest_casting_batches(&orders, &rows, &MetalType::Silver, 7, 12.0, Tz::UTC);
+        let batches: Vec<Vec<(&str, u32)>> = plan
+            .batches
+            .iter()
+            .map(|b| {
+                b.pieces
+                    .iter()
+                    .map(|p| (p.order_id.as_str(), p.quantity))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(batches, vec![vec![("a", 2)], vec![("a", 1), ("b", 1)]]);
+        assert_eq!(plan.batches[0].total_weight_g, 10.0);
+        assert_eq!(plan.needs_data.len(), 1);
+        assert_eq!(plan.needs_data[0].order_id, "d");
+    }
+}
@@ -1,18 +1,92 @@
 //! Shopify API client: fetch orders and map to shared [crate::model] types.
 
+use crate::http_util::{is_retryable_status, send_with_retry, FetchError, DEFAULT_MAX_ATTEMPTS};
 use crate::log;
 use chrono::{DateTime, Duration, Utc};
 use serde::Deserialize;
+use std::collections::HashMap;
 
-use crate::model::{MetalType, Order, OrderItem, OrderSource};
+use crate::model::{
+    clamp_future_order_date, MetalType, Order, OrderItem, OrderSource, OrderStatus, ShippingAddress,
+};
 
+/// Admin API version used when `SHOPIFY_URL` doesn't name one.
+const DEFAULT_API_VERSION: &str = "2024-10";
+
+/// Admin API base (`https://{shop}/admin/api/{version}`) from `SHOPIFY_URL`, or empty when
+/// unset or malformed ([check_shopify_config] reports why).
 fn shopify_url() -> String {
-    std::env::var("SHOPIFY_URL").unwrap_or_default()
+    normalize_shopify_url(&std::env::var("SHOPIFY_URL").unwrap_or_default()).unwrap_or_default()
 }
 fn shopify_access_token() -> String {
     std::env::var("SHOPIFY_ACCESS_TOKEN").unwrap_or_default()
 }
 
+/// Both credentials are set and `SHOPIFY_URL` is usable.
+fn check_shopify_config() -> Result<(), String> {
+    let raw = std::env::var("SHOPIFY_URL").unwrap_or_default();
+    if raw.trim().is_empty() || shopify_access_token().trim().is_empty() {
+        return Err("Shopify not configured: set SHOPIFY_URL and SHOPIFY_ACCESS_TOKEN".to_string());
+    }
+    normalize_shopify_url(&raw).map(|_| ())
+}
+
+/// Turn the accepted `SHOPIFY_URL` forms into the admin API base:
+/// `myshop.myshopify.com`, `https://myshop.myshopify.com/`, `.../admin`, or a full
+/// `.../admin/api/2024-01` base (anything after the version, like `/orders.json`, is dropped).
+/// Always https; the version defaults to [DEFAULT_API_VERSION].
+fn normalize_shopify_url(raw: &str) -> Result<String, String> {
+    let invalid = |why: &str| {
+        Err(format!(
+            "SHOPIFY_URL {:?} {}; expected e.g. myshop.myshopify.com or https://myshop.myshopify.com/admin/api/{}",
+            raw.trim(),
+            why,
+            DEFAULT_API_VERSION
+        ))
+    };
+    let trimmed = raw.trim();
+    let rest = match trimmed.split_once("://") {
+        Some(("https" | "http", rest)) => rest,
+        Some(_) => return invalid("has an unsupported scheme"),
+        None => trimmed,
+    };
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let host_ok = host.contains('.')
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'));
+    if !host_ok {
+        return invalid("is not a shop address");
+    }
+    let path = path.split(['?', '#']).next().unwrap_or("");
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let version = match segments.as_slice() {
+        [] | ["admin"] | ["admin", "api"] => DEFAULT_API_VERSION,
+        ["admin", "api", version, ..] if is_api_version(version) => version,
+        ["admin", "api", _, ..] => return invalid("has an unrecognized API version (expected YYYY-MM)"),
+        _ => return invalid("has a path that isn't the Shopify admin API"),
+    };
+    Ok(format!("https://{}/admin/api/{}", host.to_lowercase(), version))
+}
+
+/// Order page in the Shopify admin, from the shop domain in `SHOPIFY_URL`.
+fn admin_order_url(order_id: &str) -> Option<String> {
+    let base = shopify_url();
+    let host = base.strip_prefix("https://")?.split('/').next()?;
+    Some(format!("https://{}/admin/orders/{}", host, order_id))
+}
+
+/// `YYYY-MM` release names, or `unstable`.
+fn is_api_version(v: &str) -> bool {
+    if v == "unstable" {
+        return true;
+    }
+    let bytes = v.as_bytes();
+    bytes.len() == 7
+        && bytes[4] == b'-'
+        && bytes.iter().enumerate().all(|(i, b)| i == 4 || b.is_ascii_digit())
+}
+
 // ---------------------------------------------------------------------------
 // Shopify API response types
 // ---------------------------------------------------------------------------
@@ -32,6 +106,12 @@ struct ShopifyOrder {
     total_price: String,
     currency: String,
     fulfillment_status: Option<String>,
+    #[serde(default)]
+    financial_status: Option<String>,
+    #[serde(default)]
+    test: bool,
+    #[serde(default)]
+    cancelled_at: Option<String>,
     shipping_address: Option<ShopifyAddress>,
 }
 
@@ -48,6 +128,22 @@ struct ShopifyLineItem {
     price: String,
     variant_title: Option<String>,
     properties: Option<Vec<ShopifyProperty>>,
+    #[serde(default)]
+    product_id: Option<i64>,
+    /// "fulfilled", "partial", or null when nothing has shipped.
+    #[serde(default)]
+    fulfillment_status: Option<String>,
+    /// Location the item ships from, as sent with the order (REST).
+    #[serde(default)]
+    origin_location: Option<ShopifyLocationRef>,
+    /// Not part of the payload; the location name from `origin_location` (REST) or the
+    /// item's fulfillment order (GraphQL).
+    #[serde(default)]
+    location: Option<String>,
+    /// Not part of the line item payload; filled in from the product (REST) or by the
+    /// GraphQL backend.
+    #[serde(default)]
+    image_url: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -56,12 +152,34 @@ struct ShopifyProperty {
     value: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct ShopifyProductsResponse {
+    products: Vec<ShopifyProduct>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShopifyProduct {
+    id: i64,
+    image: Option<ShopifyImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShopifyImage {
+    src: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct ShopifyAddress {
+    #[serde(default)]
+    name: Option<String>,
     address1: Option<String>,
+    #[serde(default)]
+    address2: Option<String>,
     city: Option<String>,
     province: Option<String>,
     country: Option<String>,
+    #[serde(default, alias = "countryCodeV2")]
+    country_code: Option<String>,
     zip: Option<String>,
 }
 
@@ -96,111 +214,1120 @@ fn extract_ring_size(name: &str, properties: &Option<Vec<ShopifyProperty>>) -> O
     None
 }
 
+/// Normalize Shopify's fulfillment status; a cancelled order is `Cancelled` and a fully
+/// refunded one `Refunded`, whatever the fulfillment says.
+fn map_shopify_status(
+    fulfillment_status: Option<&str>,
+    financial_status: Option<&str>,
+    cancelled: bool,
+) -> OrderStatus {
+    if cancelled {
+        return OrderStatus::Cancelled;
+    }
+    if financial_status == Some("refunded") {
+        return OrderStatus::Refunded;
+    }
+    match fulfillment_status {
+        None | Some("unfulfilled") | Some("open") | Some("in_progress") | Some("pending_fulfillment")
+        | Some("on_hold") | Some("scheduled") => OrderStatus::Unfulfilled,
+        Some("partial") | Some("partially_fulfilled") => OrderStatus::PartiallyFulfilled,
+        Some("fulfilled") => OrderStatus::Fulfilled,
+        Some(_) => OrderStatus::Other,
+    }
+}
+
+/// Map one Shopify REST order into the shared [Order] shape. Pure (no I/O).
+fn map_shopify_order(so: ShopifyOrder) -> Order {
+    let parsed_date = DateTime::parse_from_rfc3339(&so.created_at)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now());
+    let (order_date, raw_order_date) = clamp_future_order_date(parsed_date, Utc::now());
+    if let Some(raw) = raw_order_date {
+        log::app_log(
+            "WARN",
+            format!(
+                "Shopify: order #{} has a future created_at ({}); using now for due date",
+                so.order_number,
+                raw.to_rfc3339()
+            ),
+        );
+    }
+    let due_date = order_date + Duration::days(14);
+    let customer_name = so
+        .customer
+        .map(|c| {
+            format!(
+                "{} {}",
+                c.first_name.unwrap_or_default(),
+                c.last_name.unwrap_or_default()
+            )
+            .trim()
+            .to_string()
+        })
+        .unwrap_or_else(|| "Unknown Customer".to_string());
+
+    let items: Vec<OrderItem> = so
+        .line_items
+        .into_iter()
+        .map(|li| {
+            // Shopify's placeholder for products without variants; never a real variant.
+            let variant_title = li
+                .variant_title
+                .filter(|v| !v.trim().is_empty() && !v.trim().eq_ignore_ascii_case("Default Title"));
+            let full_name = format!(
+                "{} {}",
+                li.name,
+                variant_title.clone().unwrap_or_default()
+            );
+            let metals = MetalType::detect_all(&full_name);
+            let metal_type = metals.first().cloned().unwrap_or(MetalType::Unknown);
+            let ring_size = extract_ring_size(&full_name, &li.properties);
+            OrderItem {
+                name: li.name,
+                quantity: li.quantity as u32,
+                price: li.price.parse().unwrap_or(0.0),
+                metal_type,
+                ring_size,
+                variant_info: variant_title,
+                image_url: li.image_url,
+                metals,
+                location: li.location,
+                fulfilled: Some(li.fulfillment_status.as_deref() == Some("fulfilled")),
+            }
+        })
+        .collect();
+
+    let financial_status = so
+        .financial_status
+        .map(|f| f.trim().to_lowercase())
+        .filter(|f| !f.is_empty());
+
+    let shipping_address = so.shipping_address.map(|addr| ShippingAddress {
+        name: addr.name,
+        line1: addr.address1,
+        line2: addr.address2,
+        city: addr.city,
+        region: addr.province,
+        postal_code: addr.zip,
+        country: addr.country,
+        country_code: addr.country_code,
+    });
+
+    let location = {
+        let locations: Vec<&str> = items.iter().filter_map(|i| i.location.as_deref()).collect();
+        match locations.first() {
+            Some(first) if locations.iter().all(|l| l == first) => Some(first.to_string()),
+            _ => None,
+        }
+    };
+
+    Order {
+        id: so.id.to_string(),
+        source: OrderSource::Shopify,
+        order_number: format!("#{}", so.order_number),
+        customer_name,
+        items,
+        order_date,
+        due_date,
+        total_price: so.total_price.parse().unwrap_or(0.0),
+        currency: so.currency,
+        status: map_shopify_status(
+            so.fulfillment_status.as_deref(),
+            financial_status.as_deref(),
+            so.cancelled_at.is_some(),
+        ),
+        financial_status,
+        shipping_address,
+        computed_due_date: None,
+        location,
+        raw_order_date,
+        is_test: so.test,
+        admin_url: None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Pagination
+// ---------------------------------------------------------------------------
+
+/// How to walk multiple pages of `/orders.json`. Selected with `SHOPIFY_PAGINATION`
+/// (`link` or `since_id`); defaults to cursor-based Link headers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ShopifyPagination {
+    /// Follow the `rel="next"` URL from the `Link` response header (page_info cursors).
+    LinkHeader,
+    /// Request `since_id=<max id of previous page>` until a short page comes back.
+    SinceId,
+}
+
+impl ShopifyPagination {
+    fn from_env() -> Self {
+        match std::env::var("SHOPIFY_PAGINATION")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "since_id" | "sinceid" => ShopifyPagination::SinceId,
+            _ => ShopifyPagination::LinkHeader,
+        }
+    }
+}
+
+const PAGE_LIMIT: usize = 250;
+
+/// Extract the `rel="next"` URL from a Shopify `Link` header, if any.
+fn next_page_url(link_header: &str) -> Option<String> {
+    link_header.split(',').find_map(|part| {
+        let (url_part, rel_part) = part.split_once(';')?;
+        if !rel_part.contains("rel=\"next\"") {
+            return None;
+        }
+        let url = url_part.trim().trim_start_matches('<').trim_end_matches('>');
+        (!url.is_empty()).then(|| url.to_string())
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Product images
+// ---------------------------------------------------------------------------
+
+/// Shopify caps `ids=` on /products.json at 250 per request.
+const PRODUCT_IDS_PER_REQUEST: usize = 250;
+
+/// Main image URL per product, fetched in batches via `/products.json?ids=...`.
+/// Products without an image are left out; a failed batch is logged and skipped,
+/// so images never block the order list.
+async fn fetch_product_image_urls(client: &reqwest::Client, product_ids: &[i64]) -> HashMap<i64, String> {
+    let mut out = HashMap::new();
+    for chunk in product_ids.chunks(PRODUCT_IDS_PER_REQUEST) {
+        let ids: Vec<String> = chunk.iter().map(i64::to_string).collect();
+        let url = format!(
+            "{}/products.json?ids={}&limit={}&fields=id,image",
+            shopify_url(),
+            ids.join(","),
+            chunk.len()
+        );
+        let request = client
+            .get(&url)
+            .header("X-Shopify-Access-Token", shopify_access_token());
+        let page = match send_with_retry(request, DEFAULT_MAX_ATTEMPTS, is_retryable_status).await {
+            Ok(r) if r.status().is_success() => r.json::<ShopifyProductsResponse>().await.map_err(|e| e.to_string()),
+            Ok(r) => Err(format!("HTTP {}", r.status())),
+            Err(e) => Err(e.to_string()),
+        };
+        match page {
+            Ok(page) => out.extend(
+                page.products
+                    .into_iter()
+                    .filter_map(|p| p.image.map(|img| (p.id, img.src)))
+                    .filter(|(_, src)| !src.is_empty()),
+            ),
+            Err(e) => log::app_log(
+                "ERROR",
+                format!("Shopify: product images for {} products failed: {}", chunk.len(), e),
+            ),
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Locations & fulfillment
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+struct ShopifyFulfillmentOrdersResponse {
+    fulfillment_orders: Vec<ShopifyFulfillmentOrder>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShopifyFulfillmentOrder {
+    id: i64,
+    status: String,
+    assigned_location_id: Option<i64>,
+    #[serde(default)]
+    assigned_location: Option<ShopifyLocationRef>,
+}
+
+impl ShopifyFulfillmentOrder {
+    /// Still waiting to ship (what [mark_shopify_fulfilled] fulfills).
+    fn is_open(&self) -> bool {
+        matches!(self.status.as_str(), "open" | "in_progress")
+    }
+
+    fn location_name(&self) -> Option<&str> {
+        self.assigned_location.as_ref().and_then(|l| l.name.as_deref())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ShopifyLocationRef {
+    name: Option<String>,
+}
+
+async fn fetch_fulfillment_orders(
+    client: &reqwest::Client,
+    order_id: &str,
+) -> Result<Vec<ShopifyFulfillmentOrder>, FetchError> {
+    let request = client
+        .get(format!("{}/orders/{}/fulfillment_orders.json", shopify_url(), order_id))
+        .header("X-Shopify-Access-Token", shopify_access_token());
+    let response = send_with_retry(request, DEFAULT_MAX_ATTEMPTS, is_retryable_status)
+        .await
+        .map_err(|e| FetchError::Network(format!("Shopify request failed: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(FetchError::from_response(response, "Shopify API error").await);
+    }
+    let page: ShopifyFulfillmentOrdersResponse = response
+        .json()
+        .await
+        .map_err(|e| FetchError::Parse(format!("Failed to parse Shopify fulfillment orders: {}", e)))?;
+    Ok(page.fulfillment_orders)
+}
+
+/// Mark a Shopify order's open fulfillment orders as fulfilled, one fulfillment per
+/// assigned location (Shopify rejects fulfillments that mix locations). With `location`,
+/// only that location's part is fulfilled, leaving the rest of a split order open.
+/// Needs the `write_merchant_managed_fulfillment_orders` access scope. Returns the number
+/// of fulfillment orders still open afterwards (those at other locations).
+pub async fn mark_shopify_fulfilled(order_id: &str, location: Option<&str>) -> Result<usize, FetchError> {
+    check_shopify_config().map_err(FetchError::NotConfigured)?;
+    let client = reqwest::Client::new();
+    let fulfillment_orders = fetch_fulfillment_orders(&client, order_id).await?;
+
+    // Open fulfillment orders grouped by assigned location, in first-seen order.
+    let mut by_location: Vec<(Option<i64>, Vec<i64>)> = Vec::new();
+    let mut still_open = 0;
+    for fo in fulfillment_orders {
+        if !fo.is_open() {
+            continue;
+        }
+        if location.is_some_and(|wanted| fo.location_name() != Some(wanted)) {
+            still_open += 1;
+            continue;
+        }
+        match by_location.iter_mut().find(|(loc, _)| *loc == fo.assigned_location_id) {
+            Some((_, ids)) => ids.push(fo.id),
+            None => by_location.push((fo.assigned_location_id, vec![fo.id])),
+        }
+    }
+    if by_location.is_empty() {
+        return Err(FetchError::Invalid(match location {
+            Some(loc) => format!("Order {} has nothing open to fulfill at {}", order_id, loc),
+            None => format!("Order {} has nothing open to fulfill", order_id),
+        }));
+    }
+
+    for (_, fulfillment_order_ids) in &by_location {
+        let body = serde_json::json!({
+            "fulfillment": {
+                "notify_customer": false,
+                "line_items_by_fulfillment_order": fulfillment_order_ids
+                    .iter()
+                    .map(|id| serde_json::json!({ "fulfillment_order_id": id }))
+                    .collect::<Vec<_>>(),
+            }
+        });
+        let request = client
+            .post(format!("{}/fulfillments.json", shopify_url()))
+            .header("X-Shopify-Access-Token", shopify_access_token())
+            .json(&body);
+        let response = send_with_retry(request, DEFAULT_MAX_ATTEMPTS, is_retryable_status)
+            .await
+            .map_err(|e| FetchError::Network(format!("Shopify request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(FetchError::from_response(response, "Shopify fulfillment failed").await);
+        }
+    }
+    log::app_log(
+        "INFO",
+        format!(
+            "Shopify: order {} fulfilled ({} location(s){})",
+            order_id,
+            by_location.len(),
+            location.map(|l| format!(", {}", l)).unwrap_or_default()
+        ),
+    );
+    Ok(still_open)
+}
+
+// ---------------------------------------------------------------------------
+// Backend selection
+// ---------------------------------------------------------------------------
+
+/// Which Shopify Admin API to read orders from. Selected with `SHOPIFY_API`
+/// (`rest` or `graphql`); defaults to REST.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ShopifyBackend {
+    Rest,
+    /// One query per page returns orders, line items, variant images and fulfillment
+    /// orders (for item locations) together. Needs the `read_merchant_managed_fulfillment_orders`
+    /// access scope on top of `read_orders`.
+    GraphQl,
+}
+
+impl ShopifyBackend {
+    fn from_env() -> Self {
+        match std::env::var("SHOPIFY_API")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "graphql" | "gql" => ShopifyBackend::GraphQl,
+            _ => ShopifyBackend::Rest,
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Public API
 // ---------------------------------------------------------------------------
 
-/// Fetch orders from Shopify (last 60 days, any status).
-pub async fn fetch_shopify_orders() -> Result<Vec<Order>, String> {
-    log::app_log("INFO", "Shopify: requesting orders (last 60 days)...");
+/// How a [fetch_shopify_orders] error reads in the UI: just the status, since Shopify's
+/// error bodies (often an HTML page) were never shown.
+pub fn fetch_error_message(e: &FetchError) -> String {
+    e.status_message()
+}
+
+/// Fetch orders from Shopify created in the last `lookback_days`, following pagination.
+/// With `include_shipped == false` only unfulfilled / partially fulfilled orders are returned.
+/// Uses the REST API unless `SHOPIFY_API=graphql`.
+pub async fn fetch_shopify_orders(lookback_days: i64, include_shipped: bool) -> Result<Vec<Order>, FetchError> {
+    check_shopify_config().map_err(FetchError::NotConfigured)?;
+    let mut raw_orders = match ShopifyBackend::from_env() {
+        ShopifyBackend::Rest => fetch_rest_orders(lookback_days, include_shipped).await?,
+        ShopifyBackend::GraphQl => fetch_graphql_orders(lookback_days, include_shipped).await?,
+    };
+
+    // Pagination can overlap at page boundaries if orders arrive mid-fetch; keep one per id,
+    // in ascending id order so the result is stable regardless of backend or strategy.
+    raw_orders.sort_by_key(|o| o.id);
+    raw_orders.dedup_by_key(|o| o.id);
+
+    log::app_log("INFO", format!("Shopify: got {} orders, mapping...", raw_orders.len()));
+
+    let orders = raw_orders
+        .into_iter()
+        .map(|so| {
+            let mut order = map_shopify_order(so);
+            order.admin_url = admin_order_url(&order.id);
+            order
+        })
+        .collect();
+
+    Ok(orders)
+}
+
+// ---------------------------------------------------------------------------
+// REST backend
+// ---------------------------------------------------------------------------
+
+async fn fetch_rest_orders(lookback_days: i64, include_shipped: bool) -> Result<Vec<ShopifyOrder>, FetchError> {
+    let strategy = ShopifyPagination::from_env();
+    log::app_log(
+        "INFO",
+        format!(
+            "Shopify: requesting orders (last {} days, include_shipped={}, pagination={:?})...",
+            lookback_days, include_shipped, strategy
+        ),
+    );
     let client = reqwest::Client::new();
-    let two_months_ago = Utc::now() - Duration::days(60);
-    let created_at_min = two_months_ago.format("%Y-%m-%dT%H:%M:%S%:z").to_string();
-    let url = format!(
-        "{}/orders.json?status=any&limit=250&created_at_min={}",
+    let window_start = Utc::now() - Duration::days(lookback_days);
+    let created_at_min = window_start.format("%Y-%m-%dT%H:%M:%S%:z").to_string();
+    let fulfillment_filter = if include_shipped { "" } else { "&fulfillment_status=unfulfilled" };
+    let first_url = format!(
+        "{}/orders.json?status=any&limit={}&created_at_min={}{}",
         shopify_url(),
-        created_at_min
+        PAGE_LIMIT,
+        created_at_min,
+        fulfillment_filter
     );
 
-    let response = client
-        .get(&url)
-        .header("X-Shopify-Access-Token", shopify_access_token())
-        .header("Content-Type", "application/json")
-        .send()
-        .await
-        .map_err(|e| format!("Shopify request failed: {}", e))?;
+    let mut raw_orders: Vec<ShopifyOrder> = Vec::new();
+    let mut next_url = Some(first_url.clone());
+    while let Some(url) = next_url.take() {
+        let request = client
+            .get(&url)
+            .header("X-Shopify-Access-Token", shopify_access_token())
+            .header("Content-Type", "application/json");
+        let response = send_with_retry(request, DEFAULT_MAX_ATTEMPTS, is_retryable_status)
+            .await
+            .map_err(|e| FetchError::Network(format!("Shopify request failed: {}", e)))?;
 
-    if !response.status().is_success() {
-        return Err(format!("Shopify API error: {}", response.status()));
+        if !response.status().is_success() {
+            return Err(FetchError::from_response(response, "Shopify API error").await);
+        }
+
+        let link_header = response
+            .headers()
+            .get("link")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+
+        let page: ShopifyOrdersResponse = response
+            .json()
+            .await
+            .map_err(|e| FetchError::Parse(format!("Failed to parse Shopify response: {}", e)))?;
+        let n = page.orders.len();
+        let max_id = page.orders.iter().map(|o| o.id).max();
+        raw_orders.extend(page.orders);
+        log::app_log(
+            "INFO",
+            format!("Shopify: page got {} orders (total so far: {})", n, raw_orders.len()),
+        );
+
+        next_url = match strategy {
+            ShopifyPagination::LinkHeader => link_header.as_deref().and_then(next_page_url),
+            ShopifyPagination::SinceId => match max_id {
+                Some(id) if n >= PAGE_LIMIT => Some(format!("{}&since_id={}", first_url, id)),
+                _ => None,
+            },
+        };
     }
 
-    let shopify_response: ShopifyOrdersResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse Shopify response: {}", e))?;
+    let mut product_ids: Vec<i64> = raw_orders
+        .iter()
+        .flat_map(|o| o.line_items.iter())
+        .filter_map(|li| li.product_id)
+        .collect();
+    product_ids.sort_unstable();
+    product_ids.dedup();
+    let images = fetch_product_image_urls(&client, &product_ids).await;
+    for li in raw_orders.iter_mut().flat_map(|o| o.line_items.iter_mut()) {
+        li.image_url = li.product_id.and_then(|id| images.get(&id).cloned());
+        li.location = li.origin_location.take().and_then(|l| l.name);
+    }
 
-    log::app_log("INFO", format!("Shopify: got {} orders, mapping...", shopify_response.orders.len()));
+    Ok(raw_orders)
+}
 
-    let orders = shopify_response
-        .orders
+// ---------------------------------------------------------------------------
+// GraphQL backend
+// ---------------------------------------------------------------------------
+
+/// Orders per GraphQL page. Kept below the REST page size since each order also
+/// pulls up to `GRAPHQL_LINE_ITEMS` line items into the query cost.
+const GRAPHQL_PAGE_SIZE: usize = 50;
+const GRAPHQL_LINE_ITEMS: usize = 100;
+/// Fulfillment orders per order; one per location, plus closed ones from reroutes.
+const GRAPHQL_FULFILLMENT_ORDERS: usize = 5;
+
+const ORDERS_QUERY: &str = r#"
+query Orders($first: Int!, $lineItems: Int!, $fulfillmentOrders: Int!, $after: String, $query: String) {
+  orders(first: $first, after: $after, query: $query, sortKey: CREATED_AT) {
+    pageInfo { hasNextPage endCursor }
+    nodes {
+      legacyResourceId
+      number
+      createdAt
+      currencyCode
+      displayFulfillmentStatus
+      displayFinancialStatus
+      cancelledAt
+      test
+      totalPriceSet { shopMoney { amount } }
+      customer { firstName lastName }
+      shippingAddress { name address1 address2 city province country countryCodeV2 zip }
+      lineItems(first: $lineItems) {
+        pageInfo { hasNextPage }
+        nodes {
+          id
+          name
+          quantity
+          variantTitle
+          unfulfilledQuantity
+          originalUnitPriceSet { shopMoney { amount } }
+          customAttributes { key value }
+          image { url }
+          variant { image { url } }
+        }
+      }
+      fulfillmentOrders(first: $fulfillmentOrders) {
+        nodes {
+          status
+          assignedLocation { name }
+          lineItems(first: $lineItems) { nodes { lineItem { id } } }
+        }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Deserialize)]
+struct GqlResponse {
+    data: Option<GqlData>,
+    #[serde(default)]
+    errors: Vec<GqlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlData {
+    orders: GqlConnection<GqlOrder>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlConnection<T> {
+    page_info: GqlPageInfo,
+    nodes: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlPageInfo {
+    has_next_page: bool,
+    #[serde(default)]
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlOrder {
+    /// Numeric REST id, serialized by Shopify as a string.
+    legacy_resource_id: String,
+    number: i64,
+    created_at: String,
+    currency_code: String,
+    display_fulfillment_status: String,
+    display_financial_status: Option<String>,
+    cancelled_at: Option<String>,
+    #[serde(default)]
+    test: bool,
+    total_price_set: GqlMoneyBag,
+    customer: Option<GqlCustomer>,
+    shipping_address: Option<ShopifyAddress>,
+    line_items: GqlConnection<GqlLineItem>,
+    fulfillment_orders: GqlNodes<GqlFulfillmentOrder>,
+}
+
+/// A connection where only the nodes are selected.
+#[derive(Debug, Deserialize)]
+struct GqlNodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlFulfillmentOrder {
+    /// OPEN, IN_PROGRESS, CLOSED, ...
+    status: String,
+    assigned_location: Option<ShopifyLocationRef>,
+    line_items: GqlNodes<GqlFulfillmentOrderLineItem>,
+}
+
+impl GqlFulfillmentOrder {
+    fn is_open(&self) -> bool {
+        matches!(self.status.as_str(), "OPEN" | "IN_PROGRESS")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlFulfillmentOrderLineItem {
+    line_item: GqlId,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlId {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlMoneyBag {
+    shop_money: GqlMoney,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlMoney {
+    amount: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlCustomer {
+    first_name: Option<String>,
+    last_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlLineItem {
+    /// Global id, e.g. "gid://shopify/LineItem/123"; matches the fulfillment order line items.
+    id: String,
+    name: String,
+    quantity: i32,
+    variant_title: Option<String>,
+    #[serde(default)]
+    unfulfilled_quantity: Option<i32>,
+    original_unit_price_set: GqlMoneyBag,
+    #[serde(default)]
+    custom_attributes: Vec<GqlAttribute>,
+    image: Option<GqlImage>,
+    variant: Option<GqlVariant>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlAttribute {
+    key: String,
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlImage {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlVariant {
+    image: Option<GqlImage>,
+}
+
+/// Location name per line item id, from each fulfillment order's assigned location (the
+/// same assignment [mark_shopify_fulfilled] works from). An open fulfillment order wins
+/// over a closed one, since that's where the item still ships from.
+fn line_item_locations(fulfillment_orders: &[GqlFulfillmentOrder]) -> HashMap<&str, &str> {
+    let mut out = HashMap::new();
+    let (open, closed): (Vec<_>, Vec<_>) = fulfillment_orders.iter().partition(|fo| fo.is_open());
+    for fo in open.into_iter().chain(closed) {
+        let Some(name) = fo.assigned_location.as_ref().and_then(|l| l.name.as_deref()) else {
+            continue;
+        };
+        for li in &fo.line_items.nodes {
+            out.entry(li.line_item.id.as_str()).or_insert(name);
+        }
+    }
+    out
+}
+
+/// Convert a GraphQL order into the REST shape so both backends share [map_shopify_order].
+fn gql_to_rest_order(go: GqlOrder) -> Result<ShopifyOrder, FetchError> {
+    let id = go
+        .legacy_resource_id
+        .parse()
+        .map_err(|_| FetchError::Parse(format!("Shopify GraphQL: bad order id {:?}", go.legacy_resource_id)))?;
+    if go.line_items.page_info.has_next_page {
+        log::app_log(
+            "ERROR",
+            format!(
+                "Shopify GraphQL: order #{} has more than {} line items; extra items are not shown",
+                go.number, GRAPHQL_LINE_ITEMS
+            ),
+        );
+    }
+    // REST reports null / "partial" / "fulfilled"; GraphQL uses SCREAMING_CASE enums.
+    let fulfillment_status = match go.display_fulfillment_status.as_str() {
+        "UNFULFILLED" => None,
+        "PARTIALLY_FULFILLED" => Some("partial".to_string()),
+        other => Some(other.to_lowercase()),
+    };
+    let locations = line_item_locations(&go.fulfillment_orders.nodes);
+    let line_items = go
+        .line_items
+        .nodes
         .into_iter()
-        .map(|so| {
-            let order_date = DateTime::parse_from_rfc3339(&so.created_at)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now());
-            let due_date = order_date + Duration::days(14);
-            let customer_name = so
-                .customer
-                .map(|c| {
-                    format!(
-                        "{} {}",
-                        c.first_name.unwrap_or_default(),
-                        c.last_name.unwrap_or_default()
-                    )
-                    .trim()
-                    .to_string()
-                })
-                .unwrap_or_else(|| "Unknown Customer".to_string());
-
-            let items: Vec<OrderItem> = so
-                .line_items
-                .into_iter()
-                .map(|li| {
-                    let full_name = format!(
-                        "{} {}",
-                        li.name,
-                        li.variant_title.clone().unwrap_or_default()
-                    );
-                    let metal_type = MetalType::from_string(&full_name);
-                    let ring_size = extract_ring_size(&full_name, &li.properties);
-                    OrderItem {
-                        name: li.name,
-                        quantity: li.quantity as u32,
-                        price: li.price.parse().unwrap_or(0.0),
-                        metal_type,
-                        ring_size,
-                        variant_info: li.variant_title,
-                        image_url: None,
-                    }
-                })
-                .collect();
+        .map(|li| ShopifyLineItem {
+            location: locations.get(li.id.as_str()).map(|name| name.to_string()),
+            origin_location: None,
+            name: li.name,
+            quantity: li.quantity,
+            price: li.original_unit_price_set.shop_money.amount,
+            product_id: None,
+            // Same values REST reports for the line item.
+            fulfillment_status: match li.unfulfilled_quantity {
+                Some(0) => Some("fulfilled".to_string()),
+                Some(n) if n < li.quantity => Some("partial".to_string()),
+                _ => None,
+            },
+            variant_title: li.variant_title,
+            properties: Some(
+                li.custom_attributes
+                    .into_iter()
+                    .map(|a| ShopifyProperty {
+                        name: a.key,
+                        value: a.value.unwrap_or_default(),
+                    })
+                    .collect(),
+            ),
+            // Prefer the variant's own image (e.g. the chosen metal), else the line item's.
+            image_url: li
+                .variant
+                .and_then(|v| v.image)
+                .or(li.image)
+                .map(|img| img.url),
+        })
+        .collect();
+    Ok(ShopifyOrder {
+        id,
+        order_number: go.number,
+        created_at: go.created_at,
+        customer: go.customer.map(|c| ShopifyCustomer {
+            first_name: c.first_name,
+            last_name: c.last_name,
+        }),
+        line_items,
+        total_price: go.total_price_set.shop_money.amount,
+        currency: go.currency_code,
+        fulfillment_status,
+        // Same values as REST, just upper-cased: PARTIALLY_REFUNDED -> partially_refunded.
+        financial_status: go.display_financial_status.map(|f| f.to_lowercase()),
+        cancelled_at: go.cancelled_at,
+        test: go.test,
+        shipping_address: go.shipping_address,
+    })
+}
+
+async fn fetch_graphql_orders(lookback_days: i64, include_shipped: bool) -> Result<Vec<ShopifyOrder>, FetchError> {
+    log::app_log(
+        "INFO",
+        format!(
+            "Shopify: requesting orders via GraphQL (last {} days, include_shipped={})...",
+            lookback_days, include_shipped
+        ),
+    );
+    let client = reqwest::Client::new();
+    let url = format!("{}/graphql.json", shopify_url());
+    let window_start = Utc::now() - Duration::days(lookback_days);
+    let mut search = format!("created_at:>='{}'", window_start.format("%Y-%m-%dT%H:%M:%SZ"));
+    if !include_shipped {
+        search.push_str(" AND fulfillment_status:unfulfilled");
+    }
+
+    let mut raw_orders: Vec<ShopifyOrder> = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let body = serde_json::json!({
+            "query": ORDERS_QUERY,
+            "variables": {
+                "first": GRAPHQL_PAGE_SIZE,
+                "lineItems": GRAPHQL_LINE_ITEMS,
+                "fulfillmentOrders": GRAPHQL_FULFILLMENT_ORDERS,
+                "after": cursor,
+                "query": search,
+            },
+        });
+        let request = client
+            .post(&url)
+            .header("X-Shopify-Access-Token", shopify_access_token())
+            .json(&body);
+        let response = send_with_retry(request, DEFAULT_MAX_ATTEMPTS, is_retryable_status)
+            .await
+            .map_err(|e| FetchError::Network(format!("Shopify request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(FetchError::from_response(response, "Shopify API error").await);
+        }
 
-            let shipping_address = so.shipping_address.map(|addr| {
-                format!(
-                    "{}, {}, {} {} {}",
-                    addr.address1.unwrap_or_default(),
-                    addr.city.unwrap_or_default(),
-                    addr.province.unwrap_or_default(),
-                    addr.zip.unwrap_or_default(),
-                    addr.country.unwrap_or_default()
-                )
+        let page: GqlResponse = response
+            .json()
+            .await
+            .map_err(|e| FetchError::Parse(format!("Failed to parse Shopify GraphQL response: {}", e)))?;
+        if !page.errors.is_empty() {
+            // GraphQL reports errors (including throttling) with a 200 status.
+            let messages: Vec<String> = page.errors.into_iter().map(|e| e.message).collect();
+            if messages.iter().any(|m| m.to_lowercase().contains("throttled")) {
+                return Err(FetchError::RateLimited {
+                    context: "Shopify API error",
+                    retry_after: None,
+                });
+            }
+            return Err(FetchError::Api {
+                context: "Shopify GraphQL error",
+                status: 200,
+                body: messages.join("; "),
             });
+        }
+        let orders = page
+            .data
+            .ok_or_else(|| FetchError::Parse("Shopify GraphQL response had no data".to_string()))?
+            .orders;
+        let n = orders.nodes.len();
+        for go in orders.nodes {
+            raw_orders.push(gql_to_rest_order(go)?);
+        }
+        log::app_log(
+            "INFO",
+            format!("Shopify: page got {} orders (total so far: {})", n, raw_orders.len()),
+        );
+
+        cursor = match orders.page_info {
+            GqlPageInfo { has_next_page: true, end_cursor: Some(c) } => Some(c),
+            _ => break,
+        };
+    }
+
+    Ok(raw_orders)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A one-item REST order, with `overrides` replacing top-level fields.
+    fn order_fixture(overrides: serde_json::Value) -> ShopifyOrder {
+        let mut order = serde_json::json!({
+            "id": 5001,
+            "order_number": 1042,
+            "created_at": "2024-03-01T10:00:00-05:00",
+            "customer": { "first_name": "Ada", "last_name": "Lovelace" },
+            "line_items": [{
+                "name": "Sterling Silver Band",
+                "quantity": 2,
+                "price": "45.00",
+                "variant_title": "Size 7",
+                "properties": null
+            }],
+            "total_price": "90.00",
+            "currency": "USD",
+            "fulfillment_status": null,
+            "shipping_address": null
+        });
+        if let (Some(base), Some(extra)) = (order.as_object_mut(), overrides.as_object()) {
+            base.extend(extra.clone());
+        }
+        serde_json::from_value(order).expect("fixture should deserialize")
+    }
+
+    #[test]
+    fn maps_order_fields() {
+        let order = map_shopify_order(order_fixture(serde_json::json!({})));
+        let placed = DateTime::parse_from_rfc3339("2024-03-01T15:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(order.id, "5001");
+        assert_eq!(order.source, OrderSource::Shopify);
+        assert_eq!(order.order_number, "#1042");
+        assert_eq!(order.customer_name, "Ada Lovelace");
+        assert_eq!(order.total_price, 90.0);
+        assert_eq!(order.currency, "USD");
+        assert_eq!(order.status, OrderStatus::Unfulfilled);
+        assert_eq!(order.order_date, placed);
+        assert_eq!(order.due_date, placed + Duration::days(14));
+        assert_eq!(order.raw_order_date, None);
+        assert!(!order.is_test);
+
+        let item = &order.items[0];
+        assert_eq!(item.name, "Sterling Silver Band");
+        assert_eq!(item.quantity, 2);
+        assert_eq!(item.price, 45.0);
+        assert_eq!(item.metal_type, MetalType::Silver);
+        assert_eq!(item.ring_size.as_deref(), Some("7"));
+        assert_eq!(item.variant_info.as_deref(), Some("Size 7"));
+        assert_eq!(item.fulfilled, Some(false));
+    }
+
+    #[test]
+    fn missing_customer_is_unknown() {
+        let order = map_shopify_order(order_fixture(serde_json::json!({ "customer": null })));
+        assert_eq!(order.customer_name, "Unknown Customer");
+    }
+
+    #[test]
+    fn future_created_at_is_clamped_and_flagged() {
+        let created_at = (Utc::now() + Duration::days(3)).to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let raw = DateTime::parse_from_rfc3339(&created_at).unwrap().with_timezone(&Utc);
+        let before = Utc::now();
+        let order = map_shopify_order(order_fixture(serde_json::json!({ "created_at": created_at })));
+        let after = Utc::now();
 
-            Order {
-                id: so.id.to_string(),
-                source: OrderSource::Shopify,
-                order_number: format!("#{}", so.order_number),
-                customer_name,
-                items,
-                order_date,
-                due_date,
-                total_price: so.total_price.parse().unwrap_or(0.0),
-                currency: so.currency,
-                status: so.fulfillment_status.unwrap_or_else(|| "unfulfilled".to_string()),
-                shipping_address,
+        assert_eq!(order.raw_order_date, Some(raw));
+        assert!(order.order_date >= before && order.order_date <= after);
+        assert_eq!(order.due_date, order.order_date + Duration::days(14));
+        assert_eq!(order.days_until_due(chrono_tz::Tz::UTC), 14);
+    }
+
+    #[test]
+    fn created_at_within_tolerance_is_kept() {
+        let created_at = (Utc::now() + Duration::minutes(5)).to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let placed = DateTime::parse_from_rfc3339(&created_at).unwrap().with_timezone(&Utc);
+        let order = map_shopify_order(order_fixture(serde_json::json!({ "created_at": created_at })));
+        assert_eq!(order.order_date, placed);
+        assert_eq!(order.raw_order_date, None);
+    }
+
+    #[test]
+    fn malformed_total_price_is_zero() {
+        let order = map_shopify_order(order_fixture(serde_json::json!({ "total_price": "12,50 USD" })));
+        assert_eq!(order.total_price, 0.0);
+    }
+
+    #[test]
+    fn default_title_variant_is_dropped() {
+        let order = map_shopify_order(order_fixture(serde_json::json!({
+            "line_items": [{
+                "name": "Bronze Cuff",
+                "quantity": 1,
+                "price": "60.00",
+                "variant_title": "Default Title",
+                "properties": null
+            }]
+        })));
+        let item = &order.items[0];
+        assert_eq!(item.variant_info, None);
+        assert_eq!(item.metal_type, MetalType::Bronze);
+        assert_eq!(item.ring_size, None);
+    }
+
+    #[test]
+    fn shopify_url_bare_host() {
+        assert_eq!(
+            normalize_shopify_url("MyShop.myshopify.com").as_deref(),
+            Ok("https://myshop.myshopify.com/admin/api/2024-10")
+        );
+    }
+
+    #[test]
+    fn shopify_url_with_scheme_and_trailing_slash() {
+        assert_eq!(
+            normalize_shopify_url(" https://myshop.myshopify.com/ ").as_deref(),
+            Ok("https://myshop.myshopify.com/admin/api/2024-10")
+        );
+        assert_eq!(
+            normalize_shopify_url("http://myshop.myshopify.com/admin").as_deref(),
+            Ok("https://myshop.myshopify.com/admin/api/2024-10")
+        );
+    }
+
+    #[test]
+    fn shopify_url_full_api_base_keeps_version() {
+        assert_eq!(
+            normalize_shopify_url("https://myshop.myshopify.com/admin/api/2024-01").as_deref(),
+            Ok("https://myshop.myshopify.com/admin/api/2024-01")
+        );
+        assert_eq!(
+            normalize_shopify_url("https://myshop.myshopify.com/admin/api/2024-01/orders.json").as_deref(),
+            Ok("https://myshop.myshopify.com/admin/api/2024-01")
+        );
+    }
+
+    #[test]
+    fn shopify_url_malformed_is_rejected() {
+        for bad in [
+            "",
+            "myshop",
+            "ftp://myshop.myshopify.com",
+            "https://myshop.myshopify.com/products",
+            "https://myshop.myshopify.com/admin/api/latest",
+        ] {
+            let err = normalize_shopify_url(bad).unwrap_err();
+            assert!(err.starts_with("SHOPIFY_URL"), "{:?}: {}", bad, err);
+        }
+    }
+
+    /// A two-item GraphQL order node, with `overrides` replacing top-level fields.
+    fn gql_order_fixture(overrides: serde_json::Value) -> GqlOrder {
+        let mut order = serde_json::json!({
+            "legacyResourceId": "5001",
+            "number": 1042,
+            "createdAt": "2024-03-01T15:00:00Z",
+            "currencyCode": "USD",
+            "displayFulfillmentStatus": "PARTIALLY_FULFILLED",
+            "displayFinancialStatus": "PARTIALLY_REFUNDED",
+            "cancelledAt": null,
+            "test": false,
+            "totalPriceSet": { "shopMoney": { "amount": "135.00" } },
+            "customer": { "firstName": "Ada", "lastName": "Lovelace" },
+            "shippingAddress": null,
+            "lineItems": {
+                "pageInfo": { "hasNextPage": false, "endCursor": null },
+                "nodes": [
+                    {
+                        "id": "gid://shopify/LineItem/1",
+                        "name": "Sterling Silver Band",
+                        "quantity": 2,
+                        "variantTitle": "Size 7",
+                        "unfulfilledQuantity": 0,
+                        "originalUnitPriceSet": { "shopMoney": { "amount": "45.00" } },
+                        "customAttributes": [{ "key": "Engraving", "value": "AL" }],
+                        "image": { "url": "https://cdn.example/band.jpg" },
+                        "variant": { "image": { "url": "https://cdn.example/band-silver.jpg" } }
+                    },
+                    {
+                        "id": "gid://shopify/LineItem/2",
+                        "name": "Bronze Cuff",
+                        "quantity": 3,
+                        "variantTitle": null,
+                        "unfulfilledQuantity": 1,
+                        "originalUnitPriceSet": { "shopMoney": { "amount": "15.00" } },
+                        "customAttributes": [],
+                        "image": { "url": "https://cdn.example/cuff.jpg" },
+                        "variant": { "image": null }
+                    }
+                ]
+            },
+            "fulfillmentOrders": {
+                "nodes": [
+                    {
+                        "status": "CLOSED",
+                        "assignedLocation": { "name": "Old Studio" },
+                        "lineItems": { "nodes": [
+                            { "lineItem": { "id": "gid://shopify/LineItem/1" } },
+                            { "lineItem": { "id": "gid://shopify/LineItem/2" } }
+                        ] }
+                    },
+                    {
+                        "status": "OPEN",
+                        "assignedLocation": { "name": "Workshop" },
+                        "lineItems": { "nodes": [
+                            { "lineItem": { "id": "gid://shopify/LineItem/2" } }
+                        ] }
+                    }
+                ]
             }
-        })
-        .collect();
+        });
+        if let (Some(base), Some(extra)) = (order.as_object_mut(), overrides.as_object()) {
+            base.extend(extra.clone());
+        }
+        serde_json::from_value(order).expect("fixture should deserialize")
+    }
 
-    Ok(orders)
+    #[test]
+    fn graphql_order_maps_like_rest() {
+        let rest = gql_to_rest_order(gql_order_fixture(serde_json::json!({}))).unwrap();
+        assert_eq!(rest.fulfillment_status.as_deref(), Some("partial"));
+        assert_eq!(rest.financial_status.as_deref(), Some("partially_refunded"));
+
+        let order = map_shopify_order(rest);
+        assert_eq!(order.id, "5001");
+        assert_eq!(order.order_number, "#1042");
+        assert_eq!(order.customer_name, "Ada Lovelace");
+        assert_eq!(order.total_price, 135.0);
+        assert_eq!(order.status, OrderStatus::PartiallyFulfilled);
+
+        let band = &order.items[0];
+        assert_eq!(band.price, 45.0);
+        assert_eq!(band.ring_size.as_deref(), Some("7"));
+        assert_eq!(band.fulfilled, Some(true));
+        // The variant's image wins over the product image.
+        assert_eq!(band.image_url.as_deref(), Some("https://cdn.example/band-silver.jpg"));
+        assert_eq!(band.location.as_deref(), Some("Old Studio"));
+
+        let cuff = &order.items[1];
+        assert_eq!(cuff.fulfilled, Some(false));
+        assert_eq!(cuff.image_url.as_deref(), Some("https://cdn.example/cuff.jpg"));
+        // Still open at the workshop, so that beats the closed fulfillment order.
+        assert_eq!(cuff.location.as_deref(), Some("Workshop"));
+    }
+
+    #[test]
+    fn graphql_fulfillment_status_uses_rest_values() {
+        for (display, expected) in [("UNFULFILLED", None), ("FULFILLED", Some("fulfilled"))] {
+            let go = gql_order_fixture(serde_json::json!({ "displayFulfillmentStatus": display }));
+            let rest = gql_to_rest_order(go).unwrap();
+            assert_eq!(rest.fulfillment_status.as_deref(), expected, "{}", display);
+        }
+    }
+
+    #[test]
+    fn graphql_bad_order_id_is_a_parse_error() {
+        let go = gql_order_fixture(serde_json::json!({ "legacyResourceId": "gid://shopify/Order/5001" }));
+        assert!(matches!(gql_to_rest_order(go), Err(FetchError::Parse(_))));
+    }
 }
+